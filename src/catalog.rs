@@ -0,0 +1,605 @@
+//! The messages of a FTL file as they are written, to edit the file and write
+//! it back.
+//!
+//! Unlike [`Entry`], which holds the resolved text of a message, a catalog
+//! keeps the patterns as Fluent source, with the comments and the blank lines
+//! between the entries. A pattern which is not changed is written back as it
+//! was, so that an untouched file keeps the same bytes. New and changed
+//! patterns get the canonical layout: they start after `= ` or on the next
+//! line, and their lines are indented by four spaces, or eight for
+//! attributes.
+use crate::entry::Entry;
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::utils::{dedent_pattern, slice_offset};
+use fluent_syntax::ast;
+use regex::Regex;
+use std::fmt;
+use std::mem;
+use std::sync::OnceLock;
+
+/// A pattern as Fluent source, without the indentation of its lines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub text: String,
+    /// Whether the pattern starts on the line after `=`.
+    pub block: bool,
+    /// The source after the `=`, as it was parsed.
+    raw: Option<String>,
+}
+
+impl Pattern {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            block: false,
+            raw: None,
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        if let Some(raw) = &self.raw {
+            if self.text == dedent_pattern(raw) && self.block == is_block(raw) {
+                if !raw.starts_with(char::is_whitespace) {
+                    write!(f, " ")?;
+                }
+                return write!(f, "{}", raw);
+            }
+        }
+        for (index, line) in self.text.lines().enumerate() {
+            if index == 0 && !self.block {
+                write!(f, " {}", line)?;
+            } else if line.is_empty() {
+                writeln!(f)?;
+            } else {
+                write!(f, "\n{:indent$}{}", "", line, indent = indent)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A message or a term of a catalog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    /// The comment right above the message, with its `#`.
+    pub comment: Option<String>,
+    /// The slug, or `-name` for a term.
+    pub id: String,
+    pub value: Option<Pattern>,
+    /// The attributes by key, such as `.label`.
+    pub attrs: Vec<(String, Pattern)>,
+}
+
+impl Message {
+    pub fn new(id: &str, value: &str) -> Self {
+        Self {
+            comment: None,
+            id: id.to_string(),
+            value: Some(Pattern::new(value)),
+            attrs: vec![],
+        }
+    }
+
+    #[cfg(test)]
+    pub fn attr(&self, key: &str) -> Option<&Pattern> {
+        self.attrs.iter().find(|(k, _)| k == key).map(|(_, p)| p)
+    }
+
+    /// Split off the first message glued onto one of the patterns, with the
+    /// attributes after it.
+    fn split_glued(&mut self) -> Option<Message> {
+        let (index, start, id, rest) = self
+            .value
+            .iter()
+            .chain(self.attrs.iter().map(|(_, p)| p))
+            .enumerate()
+            .find_map(|(index, pattern)| {
+                let (start, id, rest) = glued(&pattern.text, &self.id)?;
+                Some((index, start, id, rest))
+            })?;
+        let offset = self.value.is_some() as usize;
+        let pattern = match index.checked_sub(offset) {
+            Some(attr) => &mut self.attrs[attr].1,
+            None => self.value.as_mut().unwrap(),
+        };
+        let value = Pattern::new(&pattern.text[rest..]);
+        *pattern = Pattern::new(pattern.text[..start].trim_end());
+        Some(Message {
+            comment: None,
+            id,
+            value: Some(value),
+            attrs: self.attrs.split_off(index + 1 - offset),
+        })
+    }
+}
+
+/// The message glued onto the line of a pattern of the message `slug`, such
+/// as `parse_b = ..` at the end of the text of `parse_a`: the offset where the
+/// text of `slug` ends, the glued slug and the offset of its text. Only a
+/// slug with the prefix of `slug` counts, so that an `=` in a message is not
+/// taken for one.
+fn glued(text: &str, slug: &str) -> Option<(usize, String, usize)> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"[ \t]+([a-zA-Z][a-zA-Z0-9_]*)[ \t]*=[ \t]*").unwrap());
+    let (prefix, _) = slug.split_once('_')?;
+    re.captures_iter(text).find_map(|caps| {
+        let (all, id) = (caps.get(0)?, caps.get(1)?);
+        let glued = id.as_str().strip_prefix(prefix)?.starts_with('_');
+        glued.then(|| (all.start(), id.as_str().to_string(), all.end()))
+    })
+}
+
+/// A message whose text is Fluent source, such as the messages extracted
+/// from inline attributes.
+impl From<&Entry> for Message {
+    fn from(entry: &Entry) -> Self {
+        let mut message = Message::new(&entry.slug, &entry.value);
+        for (key, value) in entry.childs.iter() {
+            message.attrs.push((key.to_string(), Pattern::new(value)));
+        }
+        message
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(comment) = &self.comment {
+            writeln!(f, "{}", comment)?;
+        }
+        write!(f, "{} =", self.id)?;
+        if let Some(value) = &self.value {
+            value.write(f, 4)?;
+        }
+        for (key, pattern) in self.attrs.iter() {
+            write!(f, "\n    {} =", key)?;
+            pattern.write(f, 8)?;
+        }
+        writeln!(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind {
+    Message(Message),
+    /// A comment of its own, `#`, `##` or `###`, as it is written.
+    Comment(String),
+    /// Source which does not parse, as it is written.
+    Junk(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub kind: ItemKind,
+    /// The blank lines after the item.
+    pub blank_lines: usize,
+}
+
+impl Item {
+    fn is_group_comment(&self) -> bool {
+        matches!(&self.kind, ItemKind::Comment(text) if text.starts_with("##") && !text.starts_with("###"))
+    }
+}
+
+/// The items of a section sorted by the slug of their message, see
+/// [`Catalog::sort`].
+fn sort_section(mut items: Vec<Item>) -> Vec<Item> {
+    let is_message = |item: &Item| matches!(item.kind, ItemKind::Message(_));
+    let head = items.iter().position(is_message);
+    let mut groups = vec![];
+    let mut group = vec![];
+    for item in items.drain(head.unwrap_or(0)..) {
+        let last = is_message(&item);
+        group.push(item);
+        if last {
+            groups.push(mem::take(&mut group));
+        }
+    }
+    let id = |group: &Vec<Item>| match &group.last().unwrap().kind {
+        ItemKind::Message(message) => message.id.clone(),
+        _ => unreachable!(),
+    };
+    groups.sort_by_cached_key(id);
+    items.extend(groups.into_iter().flatten().chain(group));
+    items
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Catalog {
+    pub items: Vec<Item>,
+    /// Whether the source does not end with a newline, it is written back
+    /// without one.
+    pub missing_newline: bool,
+}
+
+impl Catalog {
+    /// The catalog of the FTL `source`, a part which does not parse is kept
+    /// as junk.
+    pub fn parse(source: &str) -> Self {
+        let resource = match fluent_syntax::parser::parse(source) {
+            Ok(resource) => resource,
+            Err((resource, _)) => resource,
+        };
+        let line_start = |offset: usize| source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let mut items = vec![];
+        for (index, entry) in resource.body.iter().enumerate() {
+            let start = Parser::entry_start(source, entry);
+            let end = resource
+                .body
+                .get(index + 1)
+                .map_or(source.len(), |next| Parser::entry_start(source, next));
+            let content = source[start..end].trim_end();
+            let blank_lines = source[start + content.len()..end]
+                .matches('\n')
+                .count()
+                .saturating_sub(1);
+            let end = start + content.len();
+            let kind = match entry {
+                ast::Entry::Message(message) => ItemKind::Message(Message {
+                    comment: message.comment.as_ref().map(|_| {
+                        let id = line_start(slice_offset(source, message.id.name));
+                        source[start..id].trim_end().to_string()
+                    }),
+                    id: message.id.name.to_string(),
+                    value: message.value.as_ref().map(|_| {
+                        let after = slice_offset(source, message.id.name) + message.id.name.len();
+                        pattern(source, after, attr_start(source, &message.attributes, end))
+                    }),
+                    attrs: attrs(source, &message.attributes, end),
+                }),
+                ast::Entry::Term(term) => ItemKind::Message(Message {
+                    comment: term.comment.as_ref().map(|_| {
+                        let id = line_start(slice_offset(source, term.id.name));
+                        source[start..id].trim_end().to_string()
+                    }),
+                    id: format!("-{}", term.id.name),
+                    value: {
+                        let after = slice_offset(source, term.id.name) + term.id.name.len();
+                        Some(pattern(
+                            source,
+                            after,
+                            attr_start(source, &term.attributes, end),
+                        ))
+                    },
+                    attrs: attrs(source, &term.attributes, end),
+                }),
+                ast::Entry::Comment(_)
+                | ast::Entry::GroupComment(_)
+                | ast::Entry::ResourceComment(_) => ItemKind::Comment(content.to_string()),
+                ast::Entry::Junk { .. } => ItemKind::Junk(content.to_string()),
+            };
+            items.push(Item { kind, blank_lines });
+        }
+        Self {
+            items,
+            missing_newline: !source.is_empty() && !source.ends_with('\n'),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.items.iter().filter_map(|item| match &item.kind {
+            ItemKind::Message(message) => Some(message),
+            _ => None,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn message(&self, id: &str) -> Option<&Message> {
+        self.messages().find(|m| m.id == id)
+    }
+
+    pub fn message_mut(&mut self, id: &str) -> Option<&mut Message> {
+        self.items.iter_mut().find_map(|item| match &mut item.kind {
+            ItemKind::Message(message) if message.id == id => Some(message),
+            _ => None,
+        })
+    }
+
+    /// Append `message` after a blank line.
+    pub fn push(&mut self, message: Message) {
+        if let Some(last) = self.items.last_mut() {
+            last.blank_lines = last.blank_lines.max(1);
+        }
+        self.items.push(Item {
+            kind: ItemKind::Message(message),
+            blank_lines: 0,
+        });
+    }
+
+    /// Remove the message `id` with its comment. The blank lines around it
+    /// are kept, so that the messages before and after it stay apart, but
+    /// the file does not end with them.
+    pub fn remove(&mut self, id: &str) -> Option<Message> {
+        let index = self.items.iter().position(|item| match &item.kind {
+            ItemKind::Message(message) => message.id == id,
+            _ => false,
+        })?;
+        let item = self.items.remove(index);
+        let last = index == self.items.len();
+        if let Some(previous) = index.checked_sub(1).map(|i| &mut self.items[i]) {
+            previous.blank_lines = match last {
+                true => item.blank_lines,
+                false => previous.blank_lines.max(item.blank_lines),
+            };
+        }
+        match item.kind {
+            ItemKind::Message(message) => Some(message),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Split the messages which were joined onto the line of another one,
+    /// returns how many were split off. The message split off is separated
+    /// from the next one as the message it was glued onto was.
+    pub fn split_glued(&mut self) -> usize {
+        let mut count = 0;
+        let mut index = 0;
+        while index < self.items.len() {
+            let item = &mut self.items[index];
+            if let ItemKind::Message(message) = &mut item.kind {
+                if let Some(glued) = message.split_glued() {
+                    let blank_lines = item.blank_lines;
+                    let kind = ItemKind::Message(glued);
+                    self.items.insert(index + 1, Item { kind, blank_lines });
+                    count += 1;
+                }
+            }
+            index += 1;
+        }
+        count
+    }
+
+    /// Sort the messages by slug, within each section started by a `##`
+    /// group comment. A comment of its own moves with the message after it,
+    /// the comments above the first message of a section stay on top. The
+    /// last item is separated like the one before it, so that it does not end
+    /// up glued onto the message it moves in front of.
+    pub fn sort(&mut self) {
+        if let [.., before, last] = &mut self.items[..] {
+            last.blank_lines = last.blank_lines.max(before.blank_lines);
+        }
+        let mut items = mem::take(&mut self.items);
+        let mut sections = vec![];
+        while let Some(start) = items.iter().rposition(Item::is_group_comment) {
+            sections.push(items.split_off(start));
+        }
+        sections.push(items);
+        for section in sections.into_iter().rev() {
+            self.items.extend(sort_section(section));
+        }
+    }
+
+    /// Collapse the runs of blank lines between the items into a single one,
+    /// items without a blank line between them stay together, and drop the
+    /// blank lines at the end.
+    pub fn normalize_blank_lines(&mut self) {
+        for item in self.items.iter_mut() {
+            item.blank_lines = item.blank_lines.min(1);
+        }
+        if let Some(last) = self.items.last_mut() {
+            last.blank_lines = 0;
+        }
+    }
+}
+
+/// The FTL `source` at `path` in canonical form: the glued messages split,
+/// the messages sorted by slug, no more than one blank line between the
+/// entries and a newline at the end.
+pub fn format_ftl(path: &str, source: &str) -> Result<String, Error> {
+    Parser::with_path(path).parse(source)?;
+    let mut catalog = Catalog::parse(source);
+    catalog.split_glued();
+    catalog.sort();
+    catalog.normalize_blank_lines();
+    catalog.missing_newline = false;
+    Ok(catalog.to_string())
+}
+
+impl fmt::Display for Catalog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, item) in self.items.iter().enumerate() {
+            let text = match &item.kind {
+                ItemKind::Message(message) => message.to_string(),
+                ItemKind::Comment(text) | ItemKind::Junk(text) => format!("{}\n", text),
+            };
+            match self.missing_newline && index + 1 == self.items.len() {
+                true => write!(f, "{}", text.trim_end_matches('\n'))?,
+                false => write!(f, "{}", text)?,
+            }
+            for _ in 0..item.blank_lines {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Offset of the line of the first attribute, `end` without attributes.
+fn attr_start(source: &str, attributes: &[ast::Attribute<&str>], end: usize) -> usize {
+    match attributes.first() {
+        Some(attr) => {
+            let offset = slice_offset(source, attr.id.name);
+            source[..offset].rfind('\n').map_or(0, |i| i + 1)
+        }
+        None => end,
+    }
+}
+
+fn attrs(source: &str, attributes: &[ast::Attribute<&str>], end: usize) -> Vec<(String, Pattern)> {
+    attributes
+        .iter()
+        .enumerate()
+        .map(|(index, attr)| {
+            let after = slice_offset(source, attr.id.name) + attr.id.name.len();
+            let end = attr_start(source, &attributes[index + 1..], end);
+            (format!(".{}", attr.id.name), pattern(source, after, end))
+        })
+        .collect()
+}
+
+/// The pattern which follows the `=` after `after`, up to `end`.
+fn pattern(source: &str, after: usize, end: usize) -> Pattern {
+    let start = after + source[after..].find('=').map_or(0, |i| i + 1);
+    let raw = source[start..end].trim_end();
+    Pattern {
+        text: dedent_pattern(raw),
+        block: is_block(raw),
+        raw: Some(raw.to_string()),
+    }
+}
+
+fn is_block(raw: &str) -> bool {
+    raw.lines().next().unwrap_or("").trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_round_trip() {
+        for path in [
+            "tests/case1/test.ftl",
+            "tests/escapes/messages.ftl",
+            "messages.ftl",
+            "demo.ftl",
+        ] {
+            let source = fs::read_to_string(path).unwrap();
+            assert_eq!(Catalog::parse(&source).to_string(), source, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_canonical_layout() {
+        let source = "### Resource comment
+
+# attached
+parse_a   =   one
+  .label=two
+     continued
+parse_b =
+  {$n ->
+     [one] one
+    *[other] many
+  }
+-brand = Rust
+## Group
+parse_c = {
+";
+        let mut catalog = Catalog::parse(source);
+        assert_eq!(
+            catalog.to_string(),
+            "### Resource comment
+
+# attached
+parse_a =   one
+    .label = two
+     continued
+parse_b =
+  {$n ->
+     [one] one
+    *[other] many
+  }
+-brand = Rust
+## Group
+parse_c = {
+"
+        );
+        let a = catalog.message("parse_a").unwrap();
+        assert_eq!(a.comment.as_deref(), Some("# attached"));
+        assert_eq!(a.attr(".label").unwrap().text, "two\ncontinued");
+        assert!(matches!(&catalog.items[5].kind, ItemKind::Junk(j) if j == "parse_c = {"));
+
+        let b = catalog.message_mut("parse_b").unwrap();
+        b.value = Some(Pattern::new(&b.value.as_ref().unwrap().text));
+        b.attrs
+            .push((".label".to_string(), Pattern::new("one\n  two")));
+        catalog.remove("parse_a").unwrap();
+        catalog.push(Message::new("parse_d", "four"));
+        assert_eq!(
+            catalog.to_string(),
+            "### Resource comment
+
+parse_b = {$n ->
+       [one] one
+      *[other] many
+    }
+    .label = one
+          two
+-brand = Rust
+## Group
+parse_c = {
+
+parse_d = four
+"
+        );
+    }
+
+    #[test]
+    fn test_format_ftl() {
+        let source = "### Resource comment
+
+parse_b = two parse_a = one
+    .label = one label
+
+parse_c =
+    three
+
+# standalone
+
+parse_aa = {$n ->
+        [one] one
+       *[other] many
+    } (a = b)
+
+
+";
+        assert_eq!(
+            format_ftl("messages.ftl", source).unwrap(),
+            "### Resource comment
+
+parse_a = one
+    .label = one label
+
+# standalone
+
+parse_aa = {$n ->
+        [one] one
+       *[other] many
+    } (a = b)
+
+parse_b = two
+
+parse_c =
+    three
+"
+        );
+        assert_eq!(
+            format_ftl(
+                "messages.ftl",
+                "parse_b = two\nparse_a = one\n\nparse_d = four\n\n\nparse_c = three\n"
+            )
+            .unwrap(),
+            "parse_a = one\n\nparse_b = two\nparse_c = three\n\nparse_d = four\n"
+        );
+        assert_eq!(
+            format_ftl(
+                "messages.ftl",
+                "parse_d = four\n\n## Group\n\nparse_c = three\n\nparse_b = two\n\n## Other\nparse_a = one\n"
+            )
+            .unwrap(),
+            "parse_d = four\n\n## Group\n\nparse_b = two\n\nparse_c = three\n\n## Other\nparse_a = one\n"
+        );
+        let source = fs::read_to_string("tests/case1/test.ftl").unwrap();
+        let formatted = format_ftl("test.ftl", &source).unwrap();
+        assert_eq!(format_ftl("test.ftl", &formatted).unwrap(), formatted);
+        // the shipped messages are sorted, only their runs of blank lines go
+        let source = fs::read_to_string("messages.ftl").unwrap();
+        assert_eq!(
+            format_ftl("messages.ftl", &source).unwrap(),
+            source.replace("\n\n\n", "\n\n")
+        );
+    }
+}
@@ -1,41 +1,94 @@
+use serde_json::{json, Value};
+use std::fmt;
+use std::ops::Range;
+
+/// Where a message, or an attribute of one, is defined in its FTL file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: String,
+    /// The first and the last line.
+    pub lines: (usize, usize),
+    /// The byte offsets in the file.
+    pub range: Range<usize>,
+}
+
+impl Location {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "path": self.path,
+            "line": self.lines.0,
+            "end_line": self.lines.1,
+            "start": self.range.start,
+            "end": self.range.end,
+        })
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.path, self.lines.0)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Entry {
-    pub slug: String,
-    pub value: String,
-    pub childs: Vec<(String, String)>,
+    pub(crate) slug: String,
+    pub(crate) value: String,
+    pub(crate) childs: Vec<(String, String)>,
+    /// The value, by slug, or the attributes, by key, which can not be
+    /// inlined as they are, with the reason.
+    pub(crate) manual: Vec<(String, String)>,
+    /// Where the whole message is defined, unless it was not parsed.
+    pub(crate) location: Option<Location>,
+    /// Where the value, by slug, and the attributes, by key, are defined.
+    pub(crate) locations: Vec<(String, Location)>,
 }
 
 impl Entry {
-    pub fn new(slug: String, value: String) -> Self {
+    pub(crate) fn new(slug: String, value: String) -> Self {
         Self {
             slug,
             value,
             childs: Vec::new(),
+            manual: Vec::new(),
+            location: None,
+            locations: Vec::new(),
         }
     }
 
-    pub fn add_child(&mut self, slug: String, value: String) {
+    pub(crate) fn add_child(&mut self, slug: String, value: String) {
         self.childs.push((slug, value));
     }
 
-    pub fn print(&self) {
-        println!("{} = {}", self.slug, self.value);
-        for (slug, value) in self.childs.iter() {
-            println!("-- {} = {}", slug, value);
+    /// The text of the message for `slug`, or why it has to be converted by
+    /// hand.
+    pub(crate) fn resolve(&self, slug: &str) -> Option<Result<&str, &str>> {
+        let (key, text) = self.find(slug)?;
+        match self.manual.iter().find(|(k, _)| k == key) {
+            Some((_, reason)) => Some(Err(reason)),
+            None => Some(Ok(text)),
         }
-        println!("--------------\n\n");
     }
 
-    pub fn get_value_from_slug(&self, slug: &str) -> Option<String> {
-        let _format = |v: &str| -> String {
-            if v.contains("\"") {
-                format!("r#\"{}\"#", v)
-            } else {
-                format!("\"{}\"", v)
-            }
-        };
+    /// The key of the message for `slug`, the slug of the entry for its value
+    /// or the name of an attribute such as `.label`.
+    pub(crate) fn key(&self, slug: &str) -> Option<&str> {
+        self.find(slug).map(|(key, _)| key)
+    }
+
+    /// Where the message for `slug` is defined.
+    pub(crate) fn locate(&self, slug: &str) -> Option<&Location> {
+        let key = self.key(slug)?;
+        self.locations
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, l)| l)
+    }
+
+    /// The key and the text of the message for `slug`.
+    fn find(&self, slug: &str) -> Option<(&str, &str)> {
         if slug == self.slug {
-            return Some(_format(&self.value));
+            return Some((&self.slug, &self.value));
         } else {
             // remove the first part split with "_"
             let parts = slug.split("_").skip(1).collect::<Vec<_>>();
@@ -47,11 +100,11 @@ impl Entry {
                     || k == &new_slug
                     || k == &format!(".{}", new_slug)
                 {
-                    return Some(_format(&v));
+                    return Some((k, v));
                 }
             }
         }
 
-        return None;
+        None
     }
 }
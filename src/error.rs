@@ -0,0 +1,133 @@
+use crate::parser::{Duplicate, ParseError};
+use std::fmt;
+use std::io;
+
+/// A problem which does not stop the conversion, such as a slug which could
+/// not be resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The diagnostic struct or variant where the problem was found.
+    pub item: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(item: &str, message: String) -> Self {
+        Self {
+            item: item.to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.item, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    RustParse {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The syntax errors of a FTL file, and every message and attribute of it
+    /// which is defined twice.
+    FtlParse {
+        path: String,
+        errors: Vec<ParseError>,
+        duplicates: Vec<Duplicate>,
+    },
+    UnresolvedSlug {
+        path: String,
+        slug: String,
+    },
+    /// A rename to a slug which is already taken.
+    SlugExists {
+        path: String,
+        slug: String,
+    },
+    OverlappingEdit {
+        path: String,
+        line: usize,
+        column: usize,
+    },
+    Usage(String),
+}
+
+impl Error {
+    pub fn io(path: impl fmt::Display, source: io::Error) -> Self {
+        Error::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub fn rust_parse(path: &str, error: &syn::Error) -> Self {
+        let start = error.span().start();
+        Error::RustParse {
+            path: path.to_string(),
+            line: start.line,
+            column: start.column + 1,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::RustParse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path, line, column, message),
+            Error::FtlParse {
+                path,
+                errors,
+                duplicates,
+            } => {
+                let mut lines = errors
+                    .iter()
+                    .map(|e| (e.line, format!("{}:{}", path, e)))
+                    .chain(
+                        duplicates
+                            .iter()
+                            .map(|d| (d.line, format!("{}:{}", path, d))),
+                    )
+                    .collect::<Vec<_>>();
+                lines.sort_by_key(|(line, _)| *line);
+                let lines = lines.into_iter().map(|(_, l)| l).collect::<Vec<_>>();
+                write!(f, "{}", lines.join("\n"))
+            }
+            Error::UnresolvedSlug { path, slug } => {
+                write!(f, "{}: no message found for `{}`", path, slug)
+            }
+            Error::SlugExists { path, slug } => {
+                write!(f, "{}: message `{}` already exists", path, slug)
+            }
+            Error::OverlappingEdit { path, line, column } => {
+                write!(f, "{}:{}:{}: overlapping rewrite", path, line, column)
+            }
+            Error::Usage(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
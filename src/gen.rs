@@ -1,40 +1,297 @@
-#![allow(unused_variables)]
-#![allow(dead_code)]
+use crate::entry::Entry;
+use crate::error::{Diagnostic, Error};
+use crate::log::{self, Level};
 use crate::parser::Parser;
+use crate::report::{AttrRecord, DiagRecord};
+use crate::utils::{collect_rust_files, is_diagnostic_source, unified_diff};
 use crate::visitor::SynVisitor;
-use std::collections::HashMap;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fmt;
 use std::fs;
-use std::io::Error;
-
-pub fn gen_code(ftl_file: &str, errors_path: &str, output: Option<String>) -> Result<(), Error> {
-    let content = std::fs::read_to_string(&ftl_file).expect("read failed");
-    let lines = content.lines().map(|s| s.to_string()).collect::<Vec<_>>();
-    let parser = &mut Parser::new();
-    parser.parse_lines(lines.to_vec());
-
-    let code = fs::read_to_string(&errors_path)?;
-    let syntax = syn::parse_file(&code).unwrap();
-    let visitor = &mut SynVisitor {
-        errors: vec![],
-        fluent_source: HashMap::new(),
-        file_source_code: code.to_string(),
-        attrs: HashMap::new(),
-        cur_item_name: vec![],
-        cur_source: vec![],
-        path_replace: vec![],
-    };
+use std::path::{Path, PathBuf};
+
+#[derive(Default, Clone)]
+pub struct WriteOptions {
+    pub in_place: bool,
+    pub backup: Option<String>,
+    pub dry_run: bool,
+    pub diff: bool,
+}
+
+impl WriteOptions {
+    /// Whether files are only reported on and never written.
+    pub fn read_only(&self) -> bool {
+        self.dry_run || self.diff
+    }
+}
+
+#[derive(Default)]
+pub struct FileSummary {
+    pub path: String,
+    pub diagnostics: usize,
+    pub fluent_paths: usize,
+    pub changed: bool,
+    pub dry_run: bool,
+    pub warnings: Vec<Diagnostic>,
+    /// Every diagnostic of the file with the resolution of its attributes.
+    pub(crate) records: Vec<DiagRecord>,
+    /// Every `fluent::` path of the file with its message.
+    pub(crate) path_records: Vec<AttrRecord>,
+    /// The messages which the slugs of the file refer to, by entry slug and
+    /// key.
+    pub used: BTreeSet<(String, String)>,
+    /// What is left for the caller to print instead of a written file: the
+    /// unified diff of the file with `diff`, or the converted source when it
+    /// has nowhere else to go.
+    pub output: Option<String>,
+}
+
+impl FileSummary {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "path": self.path,
+            "changed": self.changed,
+            "diagnostics": self.records.iter().map(|r| r.to_json()).collect::<Vec<_>>(),
+            "fluent_paths": self.path_records.iter().map(|r| r.to_json()).collect::<Vec<_>>(),
+            "warnings": self.warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl fmt::Display for FileSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} diagnostics, {} fluent paths, {}",
+            self.path,
+            self.diagnostics,
+            self.fluent_paths,
+            match (self.changed, self.dry_run) {
+                (false, _) => "unchanged",
+                (true, false) => "rewritten",
+                (true, true) => "would be rewritten",
+            }
+        )?;
+        if !self.warnings.is_empty() {
+            write!(f, ", {} warnings", self.warnings.len())?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_ftl(ftl_file: &str) -> Result<Vec<Entry>, Error> {
+    let content = fs::read_to_string(ftl_file).map_err(|e| Error::io(ftl_file, e))?;
+    let parser = &mut Parser::with_path(ftl_file);
+    parser.parse(&content)?;
+    Ok(parser.entries.clone())
+}
+
+pub(crate) fn gen_source(
+    entries: &[Entry],
+    path: &str,
+    code: &str,
+) -> Result<(String, FileSummary), Error> {
+    let syntax = syn::parse_file(code).map_err(|e| Error::rust_parse(path, &e))?;
+    let visitor = &mut SynVisitor::new(path, code);
     visitor.init_with_syntax(&syntax);
 
-    visitor.set_fluent_source(&parser.entries);
-    let result = visitor.gen_source_code();
-    if let Some(output) = output {
-        fs::write(output, result)?;
-    } else {
-        println!("{}", result);
+    visitor.set_fluent_source(entries);
+    let result = visitor.gen_source_code()?;
+    let summary = FileSummary {
+        path: path.to_string(),
+        diagnostics: visitor.errors.len(),
+        fluent_paths: visitor
+            .path_replace
+            .iter()
+            .filter(|(p, _)| p.starts_with("fluent::"))
+            .count(),
+        changed: result != code,
+        dry_run: false,
+        warnings: visitor.warnings.clone(),
+        records: visitor.records.clone(),
+        path_records: visitor.path_records.clone(),
+        used: visitor.used.clone(),
+        output: None,
+    };
+    Ok((result, summary))
+}
+
+/// Write the converted `result` of `source` to `output`, keeping a backup of
+/// `source` when it is overwritten.
+pub(crate) fn write_output(
+    source: &Path,
+    output: &Path,
+    result: &str,
+    options: &WriteOptions,
+) -> Result<(), Error> {
+    if let Some(suffix) = &options.backup {
+        if source == output {
+            let backup = format!("{}{}", source.display(), suffix);
+            fs::copy(source, &backup).map_err(|e| Error::io(&backup, e))?;
+        }
     }
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(|e| Error::io(parent.display(), e))?;
+    }
+    fs::write(output, result).map_err(|e| Error::io(output.display(), e))?;
+    log::event(
+        Level::Info,
+        &source.display().to_string(),
+        format_args!("written to {}", output.display()),
+    );
     Ok(())
 }
 
+/// Convert one source file. The result goes to `output`, to the source itself
+/// with `--in-place`, or to the `output` of the summary when neither is
+/// given.
+pub fn gen_file(
+    entries: &[Entry],
+    path: &str,
+    output: Option<&str>,
+    options: &WriteOptions,
+) -> Result<FileSummary, Error> {
+    let code = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+    let (result, mut summary) = gen_source(entries, path, &code)?;
+    summary.dry_run = options.read_only();
+    if options.read_only() {
+        if options.diff {
+            summary.output = Some(unified_diff(path, &code, &result));
+        }
+    } else if let Some(output) = output {
+        write_output(Path::new(path), Path::new(output), &result, options)?;
+    } else if options.in_place {
+        if summary.changed {
+            write_output(Path::new(path), Path::new(path), &result, options)?;
+        }
+    } else {
+        summary.output = Some(result);
+    }
+    Ok(summary)
+}
+
+/// A converted source file which is not written yet.
+struct Converted {
+    source: PathBuf,
+    output: PathBuf,
+    code: String,
+    result: String,
+    summary: FileSummary,
+}
+
+/// Convert every diagnostic source file under `crate_root` without writing
+/// anything, the outputs go to the same relative path below `mirror`.
+fn convert_crate(
+    entries: &[Entry],
+    crate_root: &str,
+    mirror: Option<&str>,
+    options: &WriteOptions,
+) -> Result<Vec<Converted>, Error> {
+    let mut converted = vec![];
+    for path in collect_rust_files(Path::new(crate_root))? {
+        let code = fs::read_to_string(&path).map_err(|e| Error::io(path.display(), e))?;
+        if !is_diagnostic_source(&code) {
+            log::event(
+                Level::Trace,
+                &path.display().to_string(),
+                format_args!("skipped, no diagnostics"),
+            );
+            continue;
+        }
+        let (result, mut summary) = gen_source(entries, &path.display().to_string(), &code)?;
+        summary.dry_run = options.read_only();
+        let output = match mirror {
+            Some(mirror) => {
+                let relative = path.strip_prefix(crate_root).unwrap();
+                Path::new(mirror).join(relative)
+            }
+            None => path.clone(),
+        };
+        converted.push(Converted {
+            source: path,
+            output,
+            code,
+            result,
+            summary,
+        });
+    }
+    Ok(converted)
+}
+
+/// Write the changed files, or keep their diff in their summary, once every
+/// one of them was converted.
+fn write_converted(
+    converted: Vec<Converted>,
+    options: &WriteOptions,
+) -> Result<Vec<FileSummary>, Error> {
+    let mut summaries = vec![];
+    for mut file in converted {
+        if file.summary.changed && options.diff {
+            let diff = unified_diff(&file.summary.path, &file.code, &file.result);
+            file.summary.output = Some(diff);
+        } else if file.summary.changed && !options.dry_run {
+            write_output(&file.source, &file.output, &file.result, options)?;
+        }
+        summaries.push(file.summary);
+    }
+    Ok(summaries)
+}
+
+/// Convert every diagnostic source file under `crate_root`, all of them
+/// against the entries of the same `ftl_file`. Files are rewritten in place,
+/// or written to the same relative path below `mirror`. Nothing is written
+/// when one of them fails to convert.
+pub fn gen_crate(
+    ftl_file: &str,
+    crate_root: &str,
+    mirror: Option<&str>,
+    options: &WriteOptions,
+) -> Result<Vec<FileSummary>, Error> {
+    let entries = parse_ftl(ftl_file)?;
+    let converted = convert_crate(&entries, crate_root, mirror, options)?;
+    write_converted(converted, options)
+}
+
+/// Convert every `rustc_*` crate of a `compiler/` tree which has a
+/// `messages.ftl`, each crate against its own messages. Nothing is written
+/// when one of the crates fails to convert.
+pub fn gen_workspace(
+    compiler_dir: &str,
+    mirror: Option<&str>,
+    options: &WriteOptions,
+) -> Result<Vec<FileSummary>, Error> {
+    let mut crates = vec![];
+    let read_error = |e| Error::io(compiler_dir, e);
+    for entry in fs::read_dir(compiler_dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        let is_rustc_crate = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with("rustc_"));
+        if is_rustc_crate && path.join("messages.ftl").is_file() {
+            crates.push(path);
+        }
+    }
+    crates.sort();
+
+    let mut converted = vec![];
+    for crate_root in crates.iter() {
+        let ftl_file = crate_root.join("messages.ftl").display().to_string();
+        let crate_mirror = mirror.map(|m| {
+            let name = crate_root.file_name().unwrap();
+            Path::new(m).join(name).display().to_string()
+        });
+        let entries = parse_ftl(&ftl_file)?;
+        converted.extend(convert_crate(
+            &entries,
+            &crate_root.display().to_string(),
+            crate_mirror.as_deref(),
+            options,
+        )?);
+    }
+    write_converted(converted, options)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,9 +300,17 @@ mod tests {
 
     fn single_test(ftl_file: &str, code_path: &str, expected_path: &str, output_path: &str) {
         let _ = fs::remove_file(output_path);
-        let _ = gen_code(ftl_file, code_path, Some(output_path.to_string()));
+        let entries = parse_ftl(ftl_file).unwrap();
+        let _ = gen_file(
+            &entries,
+            code_path,
+            Some(output_path),
+            &WriteOptions::default(),
+        );
         let result = fs::read_to_string(output_path).unwrap();
         let expected = fs::read_to_string(expected_path).unwrap();
+        // every inlined message must still be a valid literal
+        syn::parse_file(&result).unwrap();
         if result != expected {
             // run diff to show the differences of the two files
             let res = Command::new("diff")
@@ -79,4 +344,195 @@ mod tests {
             "/tmp/path-gen.rs",
         );
     }
+
+    #[test]
+    fn test_escapes_gen() {
+        single_test(
+            "tests/escapes/messages.ftl",
+            "tests/escapes/input.rs",
+            "tests/escapes/expect.rs",
+            "/tmp/escapes-gen.rs",
+        );
+    }
+
+    #[test]
+    fn test_gen_file_output() {
+        let entries = parse_ftl("tests/case1/test.ftl").unwrap();
+        let path = "tests/path-fix/input.rs";
+        let summary = gen_file(&entries, path, None, &WriteOptions::default()).unwrap();
+        let expected = fs::read_to_string("tests/path-fix/expect.rs").unwrap();
+        assert_eq!(summary.output.unwrap(), expected);
+
+        let options = WriteOptions {
+            diff: true,
+            ..Default::default()
+        };
+        let summary = gen_file(&entries, path, None, &options).unwrap();
+        let input = fs::read_to_string(path).unwrap();
+        assert_eq!(
+            summary.output.unwrap(),
+            unified_diff(path, &input, &expected)
+        );
+    }
+
+    #[test]
+    fn test_gen_crate() {
+        let root = "/tmp/diag-convert-crate";
+        let _ = fs::remove_dir_all(root);
+        fs::create_dir_all(format!("{}/src/parse", root)).unwrap();
+        fs::copy("tests/case1/test.rs", format!("{}/src/errors.rs", root)).unwrap();
+        fs::copy(
+            "tests/path-fix/input.rs",
+            format!("{}/src/parse/mod.rs", root),
+        )
+        .unwrap();
+        fs::write(format!("{}/src/lib.rs", root), "mod errors;\n").unwrap();
+
+        // a file which fails to convert leaves every other one untouched
+        let broken = format!("{}/src/zz_broken.rs", root);
+        fs::write(&broken, "#[derive(Diagnostic)]\nstruct {\n").unwrap();
+        assert!(gen_crate("tests/case1/test.ftl", root, None, &WriteOptions::default()).is_err());
+        let input = fs::read_to_string(format!("{}/src/errors.rs", root)).unwrap();
+        assert_eq!(input, fs::read_to_string("tests/case1/test.rs").unwrap());
+        fs::remove_file(&broken).unwrap();
+
+        let summaries =
+            gen_crate("tests/case1/test.ftl", root, None, &WriteOptions::default()).unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert!(summaries.iter().all(|s| s.changed));
+        let result = fs::read_to_string(format!("{}/src/errors.rs", root)).unwrap();
+        assert_eq!(result, fs::read_to_string("tests/case1/expect.rs").unwrap());
+        let result = fs::read_to_string(format!("{}/src/parse/mod.rs", root)).unwrap();
+        assert_eq!(
+            result,
+            fs::read_to_string("tests/path-fix/expect.rs").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_gen_workspace_mirror() {
+        let root = "/tmp/diag-convert-compiler";
+        let mirror = "/tmp/diag-convert-mirror";
+        let _ = fs::remove_dir_all(root);
+        let _ = fs::remove_dir_all(mirror);
+        fs::create_dir_all(format!("{}/rustc_parse/src", root)).unwrap();
+        fs::create_dir_all(format!("{}/rustc_span/src", root)).unwrap();
+        fs::copy(
+            "tests/case1/test.ftl",
+            format!("{}/rustc_parse/messages.ftl", root),
+        )
+        .unwrap();
+        fs::copy(
+            "tests/path-fix/input.rs",
+            format!("{}/rustc_parse/src/lib.rs", root),
+        )
+        .unwrap();
+        fs::copy(
+            "tests/path-fix/input.rs",
+            format!("{}/rustc_span/src/lib.rs", root),
+        )
+        .unwrap();
+
+        // a crate which fails to convert leaves the other crates unwritten
+        fs::create_dir_all(format!("{}/rustc_span", root)).unwrap();
+        let span_ftl = format!("{}/rustc_span/messages.ftl", root);
+        fs::write(&span_ftl, "span_a = {\n").unwrap();
+        assert!(gen_workspace(root, Some(mirror), &WriteOptions::default()).is_err());
+        assert!(!Path::new(mirror).exists());
+        fs::remove_file(&span_ftl).unwrap();
+
+        let summaries = gen_workspace(root, Some(mirror), &WriteOptions::default()).unwrap();
+        assert_eq!(summaries.len(), 1);
+        let result = fs::read_to_string(format!("{}/rustc_parse/src/lib.rs", mirror)).unwrap();
+        assert_eq!(
+            result,
+            fs::read_to_string("tests/path-fix/expect.rs").unwrap()
+        );
+        let input = fs::read_to_string(format!("{}/rustc_parse/src/lib.rs", root)).unwrap();
+        assert_eq!(
+            input,
+            fs::read_to_string("tests/path-fix/input.rs").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_in_place_backup_and_dry_run() {
+        let root = "/tmp/diag-convert-in-place";
+        let _ = fs::remove_dir_all(root);
+        fs::create_dir_all(root).unwrap();
+        let path = format!("{}/input.rs", root);
+        fs::copy("tests/path-fix/input.rs", &path).unwrap();
+
+        let mut options = WriteOptions {
+            in_place: true,
+            backup: Some(".orig".to_string()),
+            dry_run: true,
+            diff: false,
+        };
+        let entries = parse_ftl("tests/case1/test.ftl").unwrap();
+        gen_file(&entries, &path, None, &options).unwrap();
+        let input = fs::read_to_string("tests/path-fix/input.rs").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), input);
+        assert!(!Path::new(&format!("{}.orig", path)).exists());
+
+        options.dry_run = false;
+        gen_file(&entries, &path, None, &options).unwrap();
+        let expected = fs::read_to_string("tests/path-fix/expect.rs").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+        assert_eq!(fs::read_to_string(format!("{}.orig", path)).unwrap(), input);
+    }
+
+    #[test]
+    fn test_json_report() {
+        let entries = parse_ftl("tests/case1/test.ftl").unwrap();
+        let code = fs::read_to_string("tests/case1/test.rs").unwrap();
+        let (_, summary) = gen_source(&entries, "test.rs", &code).unwrap();
+        let report = summary.to_json();
+        assert_eq!(report["path"], "test.rs");
+        let plus = &report["diagnostics"][0];
+        assert_eq!(plus["name"], "AmbiguousPlus");
+        assert_eq!(
+            (plus["line"].as_u64(), plus["column"].as_u64()),
+            (Some(17), Some(19))
+        );
+        assert_eq!(
+            plus["attributes"][1],
+            json!({
+                "attribute": "suggestion",
+                "slug": ".suggestion",
+                "message": "use parentheses to disambiguate",
+                "line": 20,
+                "column": 5,
+                "definition": {
+                    "path": "tests/case1/test.ftl",
+                    "line": 507,
+                    "end_line": 507,
+                    "start": 23615,
+                    "end": 23660,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let input = fs::read_to_string("tests/path-fix/input.rs").unwrap();
+        let expected = fs::read_to_string("tests/path-fix/expect.rs").unwrap();
+        let diff = unified_diff("./tests/path-fix/input.rs", &input, &expected);
+        assert!(
+            diff.starts_with("--- a/tests/path-fix/input.rs\n+++ b/tests/path-fix/input.rs\n@@ ")
+        );
+        assert!(diff.contains(
+            "-                        err.note(fluent::parse_note);\n+                        err.note(DiagnosticMessage::Str(Cow::from("
+        ));
+        assert_eq!(unified_diff("a.rs", &input, &input), "");
+
+        // absolute paths are made relative, so that the patch still applies
+        let cwd = std::env::current_dir().unwrap();
+        let absolute = cwd.join("tests/path-fix/input.rs").display().to_string();
+        let diff = unified_diff(&absolute, &input, &expected);
+        assert!(diff.starts_with("--- a/tests/path-fix/input.rs\n"));
+        let diff = unified_diff("/nonexistent/input.rs", &input, &expected);
+        assert!(diff.starts_with("--- a/nonexistent/input.rs\n+++ b/nonexistent/input.rs\n"));
+    }
 }
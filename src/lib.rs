@@ -0,0 +1,336 @@
+//! Convert rustc diagnostic slugs into inline Fluent messages.
+//!
+//! [`convert`] rewrites one Rust source against the messages of one FTL
+//! resource, [`gen_file`], [`gen_crate`] and [`gen_workspace`] drive whole
+//! files, crates and compiler trees.
+mod catalog;
+mod entry;
+mod error;
+mod gen;
+mod literal;
+pub mod log;
+mod parser;
+mod rename;
+mod report;
+mod resolve;
+mod reverse;
+mod rewrite;
+mod unused;
+mod utils;
+mod visitor;
+
+pub use catalog::format_ftl;
+pub use entry::{Entry, Location};
+pub use error::{Diagnostic, Error};
+pub use gen::{gen_crate, gen_file, gen_workspace, parse_ftl, FileSummary, WriteOptions};
+pub use parser::{Duplicate, ParseError};
+pub use rename::rename;
+pub use reverse::{crate_prefix, reverse_crate, reverse_files};
+pub use unused::{find_unused, prune_file, UnusedMessage};
+
+use parser::Parser;
+
+/// The result of converting one Rust source.
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    /// The rewritten source.
+    pub source: String,
+    /// Whether the rewritten source differs from the input.
+    pub changed: bool,
+    /// Every problem which did not stop the conversion.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Convert the Rust `source` with the messages of the FTL resource `ftl`.
+///
+/// Errors refer to the inputs as `<ftl>` and `<source>`.
+pub fn convert(ftl: &str, source: &str) -> Result<Conversion, Error> {
+    let mut parser = Parser::with_path("<ftl>");
+    parser.parse(ftl)?;
+    let (result, summary) = gen::gen_source(&parser.entries, "<source>", source)?;
+    Ok(Conversion {
+        source: result,
+        changed: summary.changed,
+        diagnostics: summary.warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let ftl = "parse_in_in_typo = expected iterable, found keyword `in`\n    .suggestion = remove the duplicated `in`\n";
+        let source = r#"#[derive(Diagnostic)]
+#[diag(parse_in_in_typo)]
+pub(crate) struct InInTypo {
+    #[primary_span]
+    pub span: Span,
+    #[suggestion(code = "", applicability = "machine-applicable")]
+    pub sugg_span: Span,
+    #[label(parse_missing)]
+    pub label_span: Span,
+}
+"#;
+        let conversion = convert(ftl, source).unwrap();
+        assert!(conversion.changed);
+        assert!(conversion
+            .source
+            .contains("#[diag(\"expected iterable, found keyword `in`\")]"));
+        assert!(conversion
+            .source
+            .contains("#[suggestion(label = \"remove the duplicated `in`\", code = \"\""));
+        assert_eq!(
+            conversion.diagnostics,
+            vec![Diagnostic::new(
+                "InInTypo",
+                "no message found for `parse_missing`, see <ftl>:1".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_convert_lint_diagnostic() {
+        let ftl = "lint_unused_qualifications = unnecessary qualification
+    .suggestion = remove the unnecessary path segments
+    .warn = this was previously accepted
+lint_ambiguous_wide_pointer = ambiguous wide pointer comparison
+    .note = the comparison includes metadata
+lint_addr_metadata = use explicit `std::ptr::eq` method
+";
+        let source = r#"#[derive(LintDiagnostic)]
+#[diag(lint_unused_qualifications)]
+#[warning]
+pub struct UnusedQualifications {
+    #[suggestion_short(style = "verbose", code = "")]
+    pub removal_span: Span,
+}
+
+#[derive(LintDiagnostic)]
+#[lint(lint_ambiguous_wide_pointer)]
+#[note_once]
+pub struct AmbiguousWidePointer {
+    #[subdiagnostic]
+    pub addr_metadata: AddrMetadata,
+}
+
+#[derive(Subdiagnostic)]
+#[multipart_suggestion_verbose(lint_addr_metadata, applicability = "maybe-incorrect")]
+pub struct AddrMetadata {
+    #[suggestion_part(code = "std::ptr::eq(")]
+    pub left: Span,
+    #[suggestion_hidden(lint_addr_metadata, code = "")]
+    pub right: Span,
+}
+"#;
+        let conversion = convert(ftl, source).unwrap();
+        assert_eq!(conversion.diagnostics, vec![]);
+        for line in [
+            "#[diag(\"unnecessary qualification\")]",
+            "#[warning(\"this was previously accepted\")]",
+            "#[suggestion_short(label = \"remove the unnecessary path segments\", style",
+            "#[lint(\"ambiguous wide pointer comparison\")]",
+            "#[note_once(\"the comparison includes metadata\")]",
+            "#[multipart_suggestion_verbose(label = \"use explicit `std::ptr::eq` method\",",
+            "#[suggestion_part(code = \"std::ptr::eq(\")]",
+            "#[suggestion_hidden(label = \"use explicit `std::ptr::eq` method\", code",
+        ] {
+            assert!(conversion.source.contains(line), "missing {}", line);
+        }
+    }
+
+    #[test]
+    fn test_convert_multipart_suggestion() {
+        let ftl = "parse_add_paren = try adding parentheses around `{$ty}` and {$rest}\n";
+        let source = r#"#[derive(Subdiagnostic)]
+#[multipart_suggestion(parse_add_paren, applicability = "machine-applicable")]
+pub(crate) struct AddParen {
+    #[suggestion_part(code = "({ty}")]
+    pub lo: Span,
+    #[suggestion_part(code = "{close})")]
+    pub hi: Span,
+    #[suggestion_part]
+    pub mid: Span,
+    pub ty: String,
+}
+"#;
+        let conversion = convert(ftl, source).unwrap();
+        let messages = conversion
+            .diagnostics
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "AddParen: `{$rest}` in the message of `#[multipart_suggestion]` is not a field",
+                "AddParen: `{close}` in the code of `hi` is not a field",
+                "AddParen: `#[suggestion_part]` of `mid` has no code",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_unknown_variables() {
+        let ftl = "parse_bad_type_plus = expected a path, not `{$ty}`
+    .label = found {$found} in {$scope}
+parse_add_paren = try adding parentheses to `{$ty}`
+parse_remove = remove the {$count ->
+        [one] bound
+       *[other] bounds
+    } of `{$name}`
+";
+        let source = r#"#[derive(Diagnostic)]
+#[diag(parse_bad_type_plus)]
+pub(crate) struct BadTypePlus {
+    pub ty: String,
+    #[skip_arg]
+    pub scope: String,
+    #[primary_span]
+    #[label]
+    pub span: Span,
+    #[subdiagnostic]
+    pub sub: BadTypePlusSub,
+}
+
+#[derive(Subdiagnostic)]
+pub(crate) enum BadTypePlusSub {
+    #[suggestion(parse_add_paren, code = "({ty})")]
+    AddParen {
+        #[primary_span]
+        span: Span,
+    },
+    #[suggestion(parse_remove, code = "")]
+    Remove {
+        #[primary_span]
+        span: Span,
+        count: usize,
+    },
+}
+"#;
+        let conversion = convert(ftl, source).unwrap();
+        let messages = conversion
+            .diagnostics
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "BadTypePlus: `{$found}` in the message of `#[label]` is not a field",
+                "BadTypePlus: `{$scope}` in the message of `#[label]` is not a field",
+                "BadTypePlusSub::Remove: `{$name}` in the message of `#[suggestion]` is not a field",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_select_expressions() {
+        let ftl = "parse_count = {$n ->
+        [one] one item
+       *[other] {$n} items
+    }
+    .label = {NUMBER($n) ->
+        [0] none
+       *[other] some
+    }
+";
+        let source = r#"#[derive(Diagnostic)]
+#[diag(parse_count)]
+pub(crate) struct Count {
+    #[label]
+    pub span: Span,
+    pub n: usize,
+}
+"#;
+        let conversion = convert(ftl, source).unwrap();
+        assert!(conversion
+            .source
+            .contains("#[diag(\"{$n ->\n    [one] one item\n   *[other] {$n} items\n}\")]"));
+        assert!(conversion.source.contains("    #[label]\n"));
+        assert_eq!(
+            conversion.diagnostics,
+            vec![Diagnostic::new(
+                "Count",
+                "`parse_count.label` selects on `NUMBER(..)` at <ftl>:5:15 and needs manual conversion"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_convert_unresolved() {
+        let ftl = "parse_sub = a subdiagnostic\n";
+        let source = r#"/// Not a diagnostic itself
+pub(crate) enum Sub {
+    #[note(parse_sub)]
+    Note {
+        #[primary_span]
+        span: Span,
+    },
+}
+
+fn f() {
+    err.note(fluent::parse_missing);
+}
+"#;
+        let conversion = convert(ftl, source).unwrap();
+        assert_eq!(
+            conversion.source,
+            source.replace("note(parse_sub)", "note(\"a subdiagnostic\")")
+        );
+        let messages = conversion
+            .diagnostics
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec!["fluent::parse_missing: no message found for `parse_missing`"]
+        );
+    }
+
+    #[test]
+    fn test_convert_error() {
+        let error = convert("parse_a = {", "").unwrap_err();
+        assert!(error.to_string().starts_with("<ftl>:1:"));
+    }
+
+    #[test]
+    fn test_convert_substring_slugs() {
+        let ftl = "parse_suggestion = suggest\nparse_suggestion_remove = remove\n";
+        let source = r#"#[derive(Diagnostic)]
+#[diag(parse_suggestion, code = "E0001")]
+pub(crate) struct First {
+    #[suggestion(parse_suggestion_remove, code = "")]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(parse_suggestion, code = "E0001")]
+pub(crate) struct Second {
+    #[suggestion(parse_suggestion_remove, code = "")]
+    pub span: Span,
+}
+
+fn f() {
+    err.note(fluent::parse_suggestion_remove);
+    err.note(fluent::parse_suggestion);
+}
+"#;
+        let conversion = convert(ftl, source).unwrap();
+        let expected = source
+            .replace("parse_suggestion_remove, code", "label = \"remove\", code")
+            .replace("parse_suggestion, code", "label = \"suggest\", code")
+            .replace(
+                "fluent::parse_suggestion_remove",
+                "DiagnosticMessage::Str(Cow::from(\"remove\"))",
+            )
+            .replace(
+                "fluent::parse_suggestion)",
+                "DiagnosticMessage::Str(Cow::from(\"suggest\")))",
+            );
+        assert_eq!(conversion.source, expected);
+    }
+}
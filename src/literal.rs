@@ -0,0 +1,76 @@
+use std::fmt::Write;
+
+/// Quote `text` as a Rust string literal which evaluates to exactly `text`.
+///
+/// Plain text becomes `"..."`. Text with quotes or backslashes becomes a raw
+/// string with as few `#` as possible, unless it has control characters,
+/// which only an escaped string can hold. Newlines are kept as they are, so
+/// that select expressions keep their layout.
+pub fn quote(text: &str) -> String {
+    let has_control = text
+        .chars()
+        .any(|c| c.is_control() && c != '\n' && c != '\t');
+    if has_control {
+        escaped(text)
+    } else if text.contains(['"', '\\']) {
+        let hashes = "#".repeat(raw_hashes(text));
+        format!("r{}\"{}\"{}", hashes, text, hashes)
+    } else {
+        format!("\"{}\"", text)
+    }
+}
+
+/// The number of `#` a raw string needs so that no `"` of `text` ends it.
+fn raw_hashes(text: &str) -> usize {
+    let mut hashes = 0;
+    for (index, _) in text.match_indices('"') {
+        let run = text[index + 1..].chars().take_while(|c| *c == '#').count();
+        hashes = hashes.max(run + 1);
+    }
+    hashes
+}
+
+fn escaped(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' | '\t' => literal.push(c),
+            '\r' => literal.push_str("\\r"),
+            '\0' => literal.push_str("\\0"),
+            c if c.is_control() => write!(literal, "\\u{{{:x}}}", c as u32).unwrap(),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> String {
+        let literal = quote(text);
+        let parsed = syn::parse_str::<syn::LitStr>(&literal).unwrap();
+        assert_eq!(parsed.value(), text, "{} does not round-trip", literal);
+        literal
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(round_trip("expected `in`"), "\"expected `in`\"");
+        assert_eq!(round_trip("escaped: `\\r`"), "r\"escaped: `\\r`\"");
+        assert_eq!(round_trip("found `\"`"), "r#\"found `\"`\"#");
+        assert_eq!(
+            round_trip("use `\"#` or `\"##`"),
+            "r###\"use `\"#` or `\"##`\"###"
+        );
+        assert_eq!(round_trip("a\r\nb\u{7}\\"), "\"a\\r\nb\\u{7}\\\\\"");
+        assert_eq!(
+            round_trip("{$n ->\n    [one] `\"`\n   *[other] `\\`\n}"),
+            "r#\"{$n ->\n    [one] `\"`\n   *[other] `\\`\n}\"#"
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Leveled logging to stderr.
+//!
+//! The library logs nothing until [`set_level`] is called, the command line
+//! shows warnings by default.
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Log nothing.
+    Quiet,
+    /// Problems which do not stop the conversion, such as unresolved slugs.
+    Warning,
+    /// What was converted.
+    Info,
+    /// Every visited item and path, only useful to debug the converter.
+    Trace,
+}
+
+impl Level {
+    /// Whether events of `level` are logged when this is the level chosen.
+    pub fn allows(self, level: Level) -> bool {
+        level != Level::Quiet && level <= self
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Quiet => "quiet",
+            Level::Warning => "warning",
+            Level::Info => "info",
+            Level::Trace => "trace",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Quiet as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> Level {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => Level::Quiet,
+        1 => Level::Warning,
+        2 => Level::Info,
+        _ => Level::Trace,
+    }
+}
+
+/// Whether events of `level` are logged.
+pub fn enabled(level: Level) -> bool {
+    self::level().allows(level)
+}
+
+/// Log `message` at `level`, `context` names where it happened, usually
+/// `path:line:column: Item`.
+pub fn event(level: Level, context: &str, message: fmt::Arguments) {
+    if enabled(level) {
+        eprintln!("{}: {}: {}", level, context, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        assert!(!Level::Quiet.allows(Level::Warning));
+        assert!(Level::Info.allows(Level::Warning) && Level::Info.allows(Level::Info));
+        assert!(!Level::Info.allows(Level::Trace) && !Level::Info.allows(Level::Quiet));
+    }
+}
@@ -1,30 +1,509 @@
-#![allow(unused_variables)]
-#![allow(dead_code)]
-mod entry;
-mod parser;
-mod utils;
-use entry::Entry;
-use std::env;
-use std::io::Error;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use diag_convert::log::{self, Level};
+use diag_convert::{
+    crate_prefix, find_unused, format_ftl, gen_crate, gen_file, gen_workspace, parse_ftl,
+    prune_file, reverse_crate, reverse_files, Error, FileSummary, WriteOptions,
+};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process;
-mod gen;
-mod visitor;
 
-fn try_main() -> Result<(), Error> {
-    let mut args = env::args_os();
-    let _ = args.next();
+/// Every source file was converted, or would be with `check`.
+const EXIT_CONVERTED: i32 = 0;
+/// The conversion failed, nothing is guaranteed to be written.
+const EXIT_FAILED: i32 = 1;
+/// The arguments are wrong, the code clap exits with on its own errors.
+const EXIT_USAGE: i32 = 2;
+/// No source file needed a change.
+const EXIT_NOTHING_TO_DO: i32 = 3;
+/// Some sources were converted, but some slugs could not be resolved.
+const EXIT_WARNINGS: i32 = 4;
+/// `unused` found messages which no source uses.
+const EXIT_UNUSED_FOUND: i32 = 5;
+/// `fmt-ftl --check` found a file which is not formatted.
+const EXIT_NEEDS_FORMAT: i32 = 6;
 
-    let path = std::env::args().nth(1).expect("No file provided");
-    let code_file_path = std::env::args().nth(2).expect("No file provided");
-    let output = std::env::args().nth(3);
-    let result = gen::gen_code(&path, &code_file_path, output);
-    Ok(())
+/// Convert rustc diagnostic slugs into inline Fluent messages.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Which events to log to stderr
+    #[arg(long, global = true, value_name = "LEVEL", default_value = "warnings")]
+    log_level: LogLevel,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LogLevel {
+    /// Log nothing but errors
+    Quiet,
+    /// Unresolved slugs and other problems of the conversion
+    Warnings,
+    /// Every resolved message and written file
+    Info,
+    /// Every visited item and path
+    Trace,
+}
+
+impl From<LogLevel> for Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Quiet => Level::Quiet,
+            LogLevel::Warnings => Level::Warning,
+            LogLevel::Info => Level::Info,
+            LogLevel::Trace => Level::Trace,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Rewrite the diagnostic slugs of the sources into inline messages
+    Convert(ConvertArgs),
+    /// Report which sources would change, without writing anything
+    Check(SourceArgs),
+    /// Print a summary of every converted source and its warnings
+    Report(ReportArgs),
+    /// List the messages of the FTL file which no source uses
+    Unused(UnusedArgs),
+    /// Extract inline messages of the sources back into a FTL file
+    Reverse(ReverseArgs),
+    /// Rename a message in the FTL file and in every source using it
+    Rename(RenameArgs),
+    /// Sort the messages of FTL files and normalize their layout
+    FmtFtl(FmtFtlArgs),
+}
+
+#[derive(Args)]
+struct SourceArgs {
+    /// The FTL file with the messages, defaults to the `messages.ftl` of
+    /// each crate
+    #[arg(long, value_name = "PATH")]
+    ftl: Option<String>,
+    /// Rust source files, crate roots or `compiler/` directories
+    #[arg(required = true, value_name = "SOURCE")]
+    sources: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ReportFormat {
+    /// One summary line per source file
+    Text,
+    /// Every diagnostic with its slugs and resolved messages, as JSON
+    Json,
+}
+
+#[derive(Args)]
+struct ReportArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// The format of the report
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    report: ReportFormat,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// Write the converted source file to this path
+    #[arg(short, long, value_name = "PATH", conflicts_with = "in_place")]
+    output: Option<String>,
+    /// Write converted crates below this directory instead of in place
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["in_place", "output"])]
+    mirror: Option<String>,
+    /// Overwrite the input source files
+    #[arg(long)]
+    in_place: bool,
+    /// Keep a copy of every overwritten file with this suffix
+    #[arg(long, value_name = "SUFFIX", requires = "in_place")]
+    backup: Option<String>,
+    /// Only report which files would change
+    #[arg(long)]
+    dry_run: bool,
+    /// Print a unified diff instead of writing any file
+    #[arg(long)]
+    diff: bool,
+    /// The format of the report of the converted files
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    report: ReportFormat,
+}
+
+#[derive(Args)]
+struct UnusedArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// Remove the unused messages from the FTL file
+    #[arg(long)]
+    prune: bool,
+}
+
+#[derive(Args)]
+struct ReverseArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// The prefix of the generated slugs, defaults to the crate name without
+    /// `rustc_`
+    #[arg(long, value_name = "PREFIX")]
+    prefix: Option<String>,
+    /// Only report which files would change
+    #[arg(long)]
+    dry_run: bool,
+    /// Print a unified diff instead of writing any file
+    #[arg(long)]
+    diff: bool,
+}
+
+#[derive(Args)]
+struct RenameArgs {
+    /// The slug of the message to rename
+    old: String,
+    /// The new slug of the message
+    new: String,
+    #[command(flatten)]
+    source: SourceArgs,
+    /// Only report which files would change
+    #[arg(long)]
+    dry_run: bool,
+    /// Print a unified diff instead of writing any file
+    #[arg(long)]
+    diff: bool,
+}
+
+#[derive(Args)]
+struct FmtFtlArgs {
+    /// FTL files, or crate roots with a `messages.ftl`
+    #[arg(required = true, value_name = "FILE")]
+    files: Vec<String>,
+    /// Only report the files which are not formatted
+    #[arg(long)]
+    check: bool,
+}
+
+/// Run the conversion over every source, a directory is a crate root when it
+/// has a `messages.ftl`, otherwise a `compiler/` tree.
+fn convert(
+    args: &SourceArgs,
+    output: Option<&str>,
+    mirror: Option<&str>,
+    options: &WriteOptions,
+) -> Result<Vec<FileSummary>, Error> {
+    let single_file = args.sources.len() == 1 && !Path::new(&args.sources[0]).is_dir();
+    if output.is_some() && !single_file {
+        return Err(Error::Usage(
+            "--output requires a single source file".to_string(),
+        ));
+    }
+    let mut summaries = vec![];
+    for source in args.sources.iter() {
+        let path = Path::new(source);
+        let crate_ftl = path.join("messages.ftl");
+        let ftl = match &args.ftl {
+            Some(ftl) => Some(ftl.to_string()),
+            None if crate_ftl.is_file() => Some(crate_ftl.display().to_string()),
+            None => None,
+        };
+        if path.is_dir() {
+            match ftl {
+                Some(ftl) => summaries.extend(gen_crate(&ftl, source, mirror, options)?),
+                None => summaries.extend(gen_workspace(source, mirror, options)?),
+            }
+        } else {
+            let Some(ftl) = ftl else {
+                return Err(Error::Usage(format!("no FTL file given for {}", source)));
+            };
+            let entries = parse_ftl(&ftl)?;
+            summaries.push(gen_file(&entries, source, output, options)?);
+        }
+    }
+    Ok(summaries)
+}
+
+/// Extract the inline messages of every source into its FTL file, a
+/// directory is a crate root.
+fn reverse(args: &ReverseArgs, options: &WriteOptions) -> Result<Vec<FileSummary>, Error> {
+    let mut summaries = vec![];
+    for source in args.source.sources.iter() {
+        let path = Path::new(source);
+        let ftl = match &args.source.ftl {
+            Some(ftl) => ftl.to_string(),
+            None if path.is_dir() => path.join("messages.ftl").display().to_string(),
+            None => return Err(Error::Usage(format!("no FTL file given for {}", source))),
+        };
+        let crate_root = match path.is_dir() {
+            true => path,
+            false => Path::new(&ftl).parent().unwrap_or(Path::new("")),
+        };
+        let prefix = match &args.prefix {
+            Some(prefix) => prefix.to_string(),
+            None => crate_prefix(&crate_root.canonicalize().unwrap_or_default())
+                .ok_or_else(|| Error::Usage(format!("no slug prefix given for {}", source)))?,
+        };
+        if path.is_dir() {
+            summaries.extend(reverse_crate(&ftl, &prefix, source, options)?);
+        } else {
+            let sources = [path.to_path_buf()];
+            summaries.extend(reverse_files(&ftl, &prefix, &sources, options)?);
+        }
+    }
+    Ok(summaries)
+}
+
+/// Report the messages which none of the sources uses, grouped by the FTL
+/// file they are defined in.
+fn unused(args: &UnusedArgs) -> Result<i32, Error> {
+    let options = WriteOptions {
+        dry_run: true,
+        ..Default::default()
+    };
+    let mut used = BTreeMap::<String, BTreeSet<(String, String)>>::new();
+    for source in args.source.sources.iter() {
+        let crate_ftl = Path::new(source).join("messages.ftl");
+        let ftl = match &args.source.ftl {
+            Some(ftl) => ftl.to_string(),
+            None if crate_ftl.is_file() => crate_ftl.display().to_string(),
+            None => return Err(Error::Usage(format!("no FTL file given for {}", source))),
+        };
+        let source_args = SourceArgs {
+            ftl: Some(ftl.clone()),
+            sources: vec![source.to_string()],
+        };
+        let uses = used.entry(ftl).or_default();
+        for summary in convert(&source_args, None, None, &options)? {
+            uses.extend(summary.used);
+        }
+    }
+
+    let mut found = false;
+    for (ftl, used) in used.iter() {
+        let source = fs::read_to_string(ftl).map_err(|e| Error::io(ftl, e))?;
+        let messages = find_unused(ftl, &source, used);
+        for message in messages.iter() {
+            println!("{}", message);
+        }
+        if args.prune && !messages.is_empty() {
+            prune_file(ftl, &messages)?;
+        }
+        found |= !messages.is_empty();
+    }
+    Ok(if found {
+        EXIT_UNUSED_FOUND
+    } else {
+        EXIT_CONVERTED
+    })
+}
+
+/// Rename a message of the one FTL file shared by every source, a
+/// directory is a crate root.
+fn rename(args: &RenameArgs, options: &WriteOptions) -> Result<Vec<FileSummary>, Error> {
+    let mut ftl_files = BTreeSet::new();
+    for source in args.source.sources.iter() {
+        let crate_ftl = Path::new(source).join("messages.ftl");
+        ftl_files.insert(match &args.source.ftl {
+            Some(ftl) => ftl.to_string(),
+            None if crate_ftl.is_file() => crate_ftl.display().to_string(),
+            None => return Err(Error::Usage(format!("no FTL file given for {}", source))),
+        });
+    }
+    if ftl_files.len() > 1 {
+        return Err(Error::Usage(
+            "the sources of a rename must share one FTL file".to_string(),
+        ));
+    }
+    let ftl = ftl_files.pop_first().unwrap();
+    let sources = args
+        .source
+        .sources
+        .iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+    diag_convert::rename(&ftl, &sources, &args.old, &args.new, options)
+}
+
+/// Format every FTL file, with `--check` fail when one of them is not
+/// formatted.
+fn fmt_ftl(args: &FmtFtlArgs) -> Result<i32, Error> {
+    let mut changed = false;
+    for file in args.files.iter() {
+        let path = match Path::new(file).is_dir() {
+            true => Path::new(file).join("messages.ftl").display().to_string(),
+            false => file.to_string(),
+        };
+        let source = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        let formatted = format_ftl(&path, &source)?;
+        if formatted == source {
+            continue;
+        }
+        changed = true;
+        if args.check {
+            println!("{}: not formatted", path);
+        } else {
+            fs::write(&path, formatted).map_err(|e| Error::io(&path, e))?;
+            println!("{}: formatted", path);
+        }
+    }
+    Ok(match (changed, args.check) {
+        (true, true) => EXIT_NEEDS_FORMAT,
+        (true, false) | (false, true) => EXIT_CONVERTED,
+        (false, false) => EXIT_NOTHING_TO_DO,
+    })
+}
+
+fn exit_code(summaries: &[FileSummary]) -> i32 {
+    if summaries.iter().any(|s| !s.warnings.is_empty()) {
+        EXIT_WARNINGS
+    } else if summaries.iter().any(|s| s.changed) {
+        EXIT_CONVERTED
+    } else {
+        EXIT_NOTHING_TO_DO
+    }
+}
+
+/// Print what the library left for stdout, the patches or the converted
+/// source of a single file.
+fn print_outputs(summaries: &[FileSummary]) {
+    for output in summaries.iter().filter_map(|s| s.output.as_deref()) {
+        print!("{}", output);
+    }
+}
+
+fn json_report(summaries: &[FileSummary]) -> String {
+    let files = summaries.iter().map(|s| s.to_json()).collect::<Vec<_>>();
+    serde_json::to_string_pretty(&json!({ "files": files })).unwrap()
+}
+
+fn try_main() -> Result<i32, Error> {
+    let cli = Cli::parse();
+    log::set_level(cli.log_level.into());
+    let summaries = match &cli.command {
+        Command::Convert(args) => {
+            let options = WriteOptions {
+                in_place: args.in_place,
+                backup: args.backup.clone(),
+                dry_run: args.dry_run,
+                diff: args.diff,
+            };
+            let summaries = convert(
+                &args.source,
+                args.output.as_deref(),
+                args.mirror.as_deref(),
+                &options,
+            )?;
+            print_outputs(&summaries);
+            // stdout carries the patch, or the converted code of a single file
+            let to_stdout = options.diff
+                || (args.output.is_none()
+                    && !args.in_place
+                    && !args.dry_run
+                    && args.source.sources.iter().all(|s| Path::new(s).is_file()));
+            let lines = match args.report {
+                ReportFormat::Text => summaries.iter().map(|s| s.to_string()).collect(),
+                ReportFormat::Json => vec![json_report(&summaries)],
+            };
+            for line in lines.iter() {
+                if to_stdout {
+                    eprintln!("{}", line);
+                } else {
+                    println!("{}", line);
+                }
+            }
+            summaries
+        }
+        Command::Check(args) => {
+            let options = WriteOptions {
+                dry_run: true,
+                ..Default::default()
+            };
+            let summaries = convert(args, None, None, &options)?;
+            for summary in summaries.iter().filter(|s| s.changed) {
+                println!("{}", summary);
+            }
+            summaries
+        }
+        Command::Report(args) => {
+            let options = WriteOptions {
+                dry_run: true,
+                ..Default::default()
+            };
+            // the report lists the warnings itself
+            if log::level() == Level::Warning {
+                log::set_level(Level::Quiet);
+            }
+            let summaries = convert(&args.source, None, None, &options)?;
+            match args.report {
+                ReportFormat::Text => {
+                    for summary in summaries.iter() {
+                        println!("{}", summary);
+                        for warning in summary.warnings.iter() {
+                            println!("    warning: {}", warning);
+                        }
+                    }
+                }
+                ReportFormat::Json => println!("{}", json_report(&summaries)),
+            }
+            summaries
+        }
+        Command::Reverse(args) => {
+            let options = WriteOptions {
+                in_place: true,
+                dry_run: args.dry_run,
+                diff: args.diff,
+                ..Default::default()
+            };
+            let summaries = reverse(args, &options)?;
+            print_outputs(&summaries);
+            for summary in summaries.iter() {
+                if options.diff {
+                    eprintln!("{}", summary);
+                } else {
+                    println!("{}", summary);
+                }
+            }
+            summaries
+        }
+        Command::Rename(args) => {
+            let options = WriteOptions {
+                in_place: true,
+                dry_run: args.dry_run,
+                diff: args.diff,
+                ..Default::default()
+            };
+            let summaries = rename(args, &options)?;
+            print_outputs(&summaries);
+            for summary in summaries.iter().filter(|s| s.changed) {
+                if options.diff {
+                    eprintln!("{}", summary);
+                } else {
+                    println!("{}", summary);
+                }
+            }
+            summaries
+        }
+        Command::FmtFtl(args) => return fmt_ftl(args),
+        Command::Unused(args) => {
+            // the conversion only finds the uses, its warnings do not matter
+            if log::level() == Level::Warning {
+                log::set_level(Level::Quiet);
+            }
+            return unused(args);
+        }
+    };
+    Ok(exit_code(&summaries))
 }
 
 fn main() {
-    if let Err(error) = try_main() {
-        let _ = writeln!(io::stderr(), "{}", error);
-        process::exit(1);
+    match try_main() {
+        Ok(code) => process::exit(code),
+        Err(error) => {
+            let _ = writeln!(io::stderr(), "{}", error);
+            match error {
+                Error::Usage(_) => process::exit(EXIT_USAGE),
+                _ => process::exit(EXIT_FAILED),
+            }
+        }
     }
 }
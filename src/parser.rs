@@ -1,14 +1,70 @@
-use crate::entry::Entry;
-use crate::utils::{self, append_to_string};
+use crate::entry::{Entry, Location};
+use crate::error::Error;
+use crate::resolve::{PatternSource, Resolver};
+use crate::utils::{line_col, slice_offset};
+use fluent_syntax::ast;
+use fluent_syntax::parser::ParserError;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(source: &str, error: &ParserError) -> Self {
+        let (line, column) = line_col(source, error.pos.start);
+        Self {
+            line,
+            column,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// A message, or an attribute of one, which is defined a second time. The
+/// first definition is the one which is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Duplicate {
+    pub slug: String,
+    /// The attribute, such as `.label`, for a duplicate attribute.
+    pub key: Option<String>,
+    pub line: usize,
+    /// The line of the first definition.
+    pub first_line: usize,
+}
+
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.key {
+            Some(_) => "attribute",
+            None => "message",
+        };
+        write!(
+            f,
+            "{}: duplicate {} `{}{}`, first defined on line {}",
+            self.line,
+            kind,
+            self.slug,
+            self.key.as_deref().unwrap_or(""),
+            self.first_line
+        )
+    }
+}
 
 #[derive(Default)]
 pub struct Parser {
-    pub entries: Vec<Entry>,
-    pub childs: Vec<(String, String)>,
-    pub cur_key: String,
-    pub cur_val: String,
-    pub parent_key: String,
-    pub parent_val: String,
+    pub(crate) entries: Vec<Entry>,
+    pub(crate) path: String,
 }
 
 impl Parser {
@@ -19,78 +75,486 @@ impl Parser {
         }
     }
 
-    fn print(&self) {
-        for entry in self.entries.iter() {
-            entry.print();
+    /// A parser for the FTL file at `path`, which is used to report errors.
+    pub fn with_path(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            ..Self::new()
         }
     }
 
-    fn add_child(&mut self) {
-        if !self.cur_key.is_empty() {
-            //eprintln!("now add child: {} => {}", self.cur_key, self.cur_val);
-            self.childs
-                .push((self.cur_key.to_string(), self.cur_val.to_string()));
+    /// Parse a Fluent resource and collect every message as an `Entry`, with
+    /// the references to other messages and terms expanded.
+    ///
+    /// Messages that parsed correctly are kept even when the resource has
+    /// errors, the errors are returned with their line and column. A message
+    /// or attribute defined twice is reported with both lines, along with the
+    /// syntax errors, only its first definition is kept.
+    pub fn parse(&mut self, source: &str) -> Result<(), Error> {
+        let (resource, errors) = match fluent_syntax::parser::parse(source) {
+            Ok(resource) => (resource, vec![]),
+            Err((resource, errors)) => (resource, errors),
+        };
+        let path = self.path.clone();
+        let mut resolver = Resolver::new(source, &path);
+        let mut messages = vec![];
+        let mut lines = HashMap::new();
+        let mut duplicates = vec![];
+        for (index, entry) in resource.body.iter().enumerate() {
+            let end = resource
+                .body
+                .get(index + 1)
+                .map_or(source.len(), |next| Self::entry_start(source, next));
+            let (id, name, value, attributes) = match entry {
+                ast::Entry::Message(message) => (
+                    message.id.name,
+                    message.id.name.to_string(),
+                    message.value.as_ref(),
+                    &message.attributes,
+                ),
+                ast::Entry::Term(term) => (
+                    term.id.name,
+                    format!("-{}", term.id.name),
+                    Some(&term.value),
+                    &term.attributes,
+                ),
+                _ => continue,
+            };
+            let line = line_col(source, slice_offset(source, id)).0;
+            if let Some(&first_line) = lines.get(&name) {
+                duplicates.push(Duplicate {
+                    slug: name,
+                    key: None,
+                    line,
+                    first_line,
+                });
+                continue;
+            }
+            duplicates.extend(Self::duplicate_attrs(source, &name, attributes));
+            lines.insert(name.clone(), line);
+            Self::add_patterns(&mut resolver, source, id, name, value, attributes, end);
+            if let ast::Entry::Message(message) = entry {
+                messages.push((message, end));
+            }
+        }
+        for (message, end) in messages {
+            let mut entry = Self::message_to_entry(&mut resolver, message);
+            self.add_locations(source, &mut entry, message, end);
+            self.entries.push(entry);
+        }
+        if errors.is_empty() && duplicates.is_empty() {
+            return Ok(());
+        }
+        Err(Error::FtlParse {
+            path: self.path.clone(),
+            errors: errors.iter().map(|e| ParseError::new(source, e)).collect(),
+            duplicates,
+        })
+    }
+
+    /// The attributes of the message `name` which are defined twice.
+    fn duplicate_attrs(
+        source: &str,
+        name: &str,
+        attributes: &[ast::Attribute<&str>],
+    ) -> Vec<Duplicate> {
+        let mut lines = HashMap::new();
+        let mut duplicates = vec![];
+        for attr in attributes.iter() {
+            let line = line_col(source, slice_offset(source, attr.id.name)).0;
+            match lines.get(attr.id.name) {
+                Some(&first_line) => duplicates.push(Duplicate {
+                    slug: name.to_string(),
+                    key: Some(format!(".{}", attr.id.name)),
+                    line,
+                    first_line,
+                }),
+                None => {
+                    lines.insert(attr.id.name, line);
+                }
+            }
         }
+        duplicates
     }
 
-    pub fn add_entry(&mut self) {
-        if self.parent_key.is_empty() {
-            return;
+    /// Register the value and the attributes of a message or term which ends
+    /// at `end`.
+    fn add_patterns<'s>(
+        resolver: &mut Resolver<'s>,
+        source: &'s str,
+        id: &'s str,
+        name: String,
+        value: Option<&'s ast::Pattern<&'s str>>,
+        attributes: &'s [ast::Attribute<&'s str>],
+        end: usize,
+    ) {
+        let attr_starts = attributes
+            .iter()
+            .map(|attr| {
+                let offset = slice_offset(source, attr.id.name);
+                source[..offset].rfind('\n').map_or(0, |i| i + 1)
+            })
+            .collect::<Vec<_>>();
+        let pattern_start = |after: usize| after + source[after..].find('=').map_or(0, |i| i + 1);
+
+        if let Some(pattern) = value {
+            let start = pattern_start(slice_offset(source, id) + id.len());
+            let end = attr_starts.first().copied().unwrap_or(end);
+            resolver.add(
+                name.clone(),
+                PatternSource {
+                    pattern,
+                    start,
+                    end,
+                },
+            );
         }
-        self.add_child();
-        let mut entry = Entry::new(self.parent_key.to_string(), self.parent_val.to_string());
-        for (k, v) in self.childs.iter() {
-            entry.add_child(k.to_string(), v.to_string());
+        for (index, attr) in attributes.iter().enumerate() {
+            let start = pattern_start(slice_offset(source, attr.id.name) + attr.id.name.len());
+            let end = attr_starts.get(index + 1).copied().unwrap_or(end);
+            let key = format!("{}.{}", name, attr.id.name);
+            resolver.add(
+                key,
+                PatternSource {
+                    pattern: &attr.value,
+                    start,
+                    end,
+                },
+            );
         }
-        //eprintln!("now add entry: {:#?}", entry);
-        if self.entries.iter().any(|e| e.slug == entry.slug) {
-            panic!("error duplicated: {:#?}", entry);
+    }
+
+    /// Record where `entry` is defined, its `message` ends before `end`.
+    fn add_locations(
+        &self,
+        source: &str,
+        entry: &mut Entry,
+        message: &ast::Message<&str>,
+        end: usize,
+    ) {
+        let location = |start: usize, end: usize| {
+            let end = start + source[start..end].trim_end().len();
+            Location {
+                path: self.path.clone(),
+                lines: (line_col(source, start).0, line_col(source, end).0),
+                range: start..end,
+            }
+        };
+        // an attribute starts at its `.`
+        let attr_starts = message
+            .attributes
+            .iter()
+            .map(|attr| slice_offset(source, attr.id.name) - 1)
+            .collect::<Vec<_>>();
+        let start = slice_offset(source, message.id.name);
+        entry.location = Some(location(start, end));
+        if message.value.is_some() {
+            let value_end = attr_starts.first().copied().unwrap_or(end);
+            entry
+                .locations
+                .push((entry.slug.clone(), location(start, value_end)));
         }
-        self.entries.push(entry);
-        self.parent_key.clear();
-        self.parent_val.clear();
-        self.cur_key.clear();
-        self.cur_val.clear();
-        self.childs.clear();
-    }
-
-    pub fn parse_lines(&mut self, lines: Vec<String>) {
-        for (index, line) in lines.iter().enumerate() {
-            let strip = line.trim();
-            //eprintln!("now strip: {}", strip);
-            if let Some((k, v)) = utils::check_kv(strip) {
-                if k.starts_with(".") {
-                    // child entry
-                    self.add_child();
-                    self.cur_key = k.to_string();
-                    self.cur_val = v.to_string();
-                } else {
-                    // new entry
-                    self.add_entry();
-                    self.parent_key = k.to_string();
-                    self.parent_val = v.to_string();
-                }
-            } else if !strip.is_empty() {
-                // continue line
-                if strip.ends_with("=") {
-                    let key = strip.trim_end_matches("=").trim();
-                    if self.parent_key.is_empty() {
-                        self.parent_key = key.to_string();
-                    } else {
-                        self.cur_key = key.to_string();
-                    }
-                } else {
-                    if self.cur_key.is_empty() {
-                        self.parent_val = append_to_string(&self.parent_val, &strip);
-                    } else {
-                        self.cur_val = append_to_string(&self.cur_val, &strip);
-                    }
-                }
-            } else {
-                // new entry
-                self.add_entry();
+        for (index, attr) in message.attributes.iter().enumerate() {
+            let key = format!(".{}", attr.id.name);
+            if entry.locations.iter().any(|(k, _)| k == &key) {
+                continue;
             }
+            let attr_end = attr_starts.get(index + 1).copied().unwrap_or(end);
+            entry
+                .locations
+                .push((key, location(attr_starts[index], attr_end)));
         }
-        self.add_entry();
+    }
+
+    fn message_to_entry(resolver: &mut Resolver, message: &ast::Message<&str>) -> Entry {
+        let slug = message.id.name.to_string();
+        let (value, manual) = match &message.value {
+            Some(_) => resolver.text(&slug),
+            None => (String::new(), None),
+        };
+        let mut entry = Entry::new(slug.clone(), value);
+        if let Some(reason) = manual {
+            entry.manual.push((slug.clone(), reason));
+        }
+        for attr in message.attributes.iter() {
+            let (value, manual) = resolver.text(&format!("{}.{}", slug, attr.id.name));
+            let key = format!(".{}", attr.id.name);
+            if entry.childs.iter().any(|(k, _)| k == &key) {
+                continue;
+            }
+            if let Some(reason) = manual {
+                entry.manual.push((key.clone(), reason));
+            }
+            entry.add_child(key, value);
+        }
+        entry
+    }
+
+    /// Offset of the line where an entry begins, including the comment
+    /// attached to a message or term.
+    pub(crate) fn entry_start(source: &str, entry: &ast::Entry<&str>) -> usize {
+        let offset = match entry {
+            ast::Entry::Message(ast::Message {
+                comment: Some(comment),
+                ..
+            })
+            | ast::Entry::Term(ast::Term {
+                comment: Some(comment),
+                ..
+            })
+            | ast::Entry::Comment(comment)
+            | ast::Entry::GroupComment(comment)
+            | ast::Entry::ResourceComment(comment) => match comment.content.first() {
+                Some(line) => slice_offset(source, line),
+                None => return source.len(),
+            },
+            ast::Entry::Message(message) => slice_offset(source, message.id.name),
+            ast::Entry::Term(term) => slice_offset(source, term.id.name),
+            ast::Entry::Junk { content } => slice_offset(source, content),
+        };
+        source[..offset].rfind('\n').map_or(0, |i| i + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Vec<Entry> {
+        let mut parser = Parser::new();
+        parser.parse(source).unwrap();
+        parser.entries
+    }
+
+    #[test]
+    fn test_parse_attributes() {
+        let entries = parse(
+            "parse_box_not_pat = expected pattern, found {$descr}\n    .note = `box` is a reserved keyword\n\n# comment\nparse_other =\n    block value\n    .label = label\n",
+        );
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].value, "expected pattern, found {$descr}");
+        assert_eq!(
+            entries[0].childs,
+            vec![(
+                ".note".to_string(),
+                "`box` is a reserved keyword".to_string()
+            )]
+        );
+        assert_eq!(entries[1].value, "block value");
+        assert_eq!(
+            entries[1].childs,
+            vec![(".label".to_string(), "label".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_select_expression() {
+        let entries = parse(
+            "parse_bare_cr = {$double_quotes ->\n    [true] bare CR\n    *[false] escaped: `\\r`\n    }\n    .escape = escape the character\n",
+        );
+        assert_eq!(
+            entries[0].value,
+            "{$double_quotes ->\n[true] bare CR\n*[false] escaped: `\\r`\n}"
+        );
+        assert_eq!(entries[0].childs[0].1, "escape the character");
+    }
+
+    #[test]
+    fn test_parse_select_needs_manual_conversion() {
+        let mut parser = Parser::with_path("messages.ftl");
+        parser
+            .parse("parse_count = {$n ->\n    [one] one\n   *[other] {NUMBER($n) ->\n        [0] none\n       *[other] many\n    }\n}\n    .label = {-brand.gender ->\n        [f] she\n       *[other] it\n    }\n")
+            .unwrap();
+        let entry = &parser.entries[0];
+        assert_eq!(
+            entry.manual,
+            vec![
+                (
+                    "parse_count".to_string(),
+                    "`parse_count` selects on `NUMBER(..)` at messages.ftl:3:14 and needs manual conversion"
+                        .to_string()
+                ),
+                (
+                    ".label".to_string(),
+                    "`parse_count.label` selects on `-brand.gender` at messages.ftl:8:16 and needs manual conversion"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            entry.resolve(".label"),
+            Some(Err(entry.manual[1].1.as_str()))
+        );
+        assert!(
+            parse("parse_ok = {$n ->\n    [one] one\n   *[other] {$n}\n}\n")[0]
+                .manual
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_references() {
+        let mut parser = Parser::with_path("messages.ftl");
+        parser
+            .parse(
+                "-brand = Rust
+    .kind = language
+-previously_accepted = this was previously accepted by {-brand}
+parse_invalid_char = invalid character
+    .label = {parse_invalid_char}: `{$ch}`
+parse_warn = {-previously_accepted}, the {-brand} compiler changed
+    .note = {parse_invalid_char.label} in {-brand(case: \"title\")}
+parse_unknown = see {-missing}
+parse_cycle_a = {parse_cycle_b}
+parse_cycle_b = before {parse_cycle_a}
+parse_uses_cycle = {parse_cycle_b}
+",
+            )
+            .unwrap();
+        let entries = parser.entries;
+        assert_eq!(entries.len(), 6);
+        assert_eq!(entries[0].childs[0].1, "invalid character: `{$ch}`");
+        assert_eq!(
+            entries[1].value,
+            "this was previously accepted by Rust, the Rust compiler changed"
+        );
+        assert_eq!(entries[1].childs[0].1, "invalid character: `{$ch}` in Rust");
+        assert!(entries[..2].iter().all(|e| e.manual.is_empty()));
+
+        assert_eq!(entries[2].value, "see {-missing}");
+        assert_eq!(
+            entries[2].manual[0].1,
+            "`parse_unknown` refers to `-missing` at messages.ftl:8:21, which is not defined"
+        );
+        assert_eq!(entries[3].value, "{parse_cycle_b}");
+        assert_eq!(
+            entries[3].manual[0].1,
+            "reference cycle `parse_cycle_a -> parse_cycle_b -> parse_cycle_a` at messages.ftl:10:24"
+        );
+        assert_eq!(entries[4].value, "before {parse_cycle_a}");
+        assert_eq!(entries[4].manual[0].1, entries[3].manual[0].1);
+        assert_eq!(entries[5].value, "{parse_cycle_b}");
+        assert_eq!(entries[5].manual[0].1, entries[3].manual[0].1);
+    }
+
+    #[test]
+    fn test_parse_term_arguments() {
+        let mut parser = Parser::new();
+        parser
+            .parse(
+                "-brand = Rust
+parse_a = {-brand(case: \")\", quote: \"}\")} compiler, {-brand(n: NUMBER(1))} edition
+",
+            )
+            .unwrap();
+        assert_eq!(parser.entries[0].value, "Rust compiler, Rust edition");
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let mut parser = Parser::new();
+        let error = parser
+            .parse("parse_ok = fine\nparse_bad = {$x ->\n    [a] b\n}\n")
+            .unwrap_err();
+        let Error::FtlParse { errors, .. } = error else {
+            panic!("unexpected error: {}", error);
+        };
+        assert_eq!(parser.entries.len(), 1);
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (4, 1));
+    }
+
+    #[test]
+    fn test_parse_non_ascii_indent() {
+        let mut parser = Parser::new();
+        parser
+            .parse("parse_a =\n    \u{2003}em space\n     one space\n")
+            .unwrap();
+        assert_eq!(parser.entries[0].value, "\u{2003}em space\n one space");
+    }
+
+    #[test]
+    fn test_parse_locations() {
+        let source = "# a comment
+parse_a =
+    one
+    .label = two
+
+parse_b = three
+";
+        let mut parser = Parser::with_path("messages.ftl");
+        parser.parse(source).unwrap();
+        let a = &parser.entries[0];
+        let location = a.location.as_ref().unwrap();
+        assert_eq!(location.to_string(), "messages.ftl:2");
+        assert_eq!(location.lines, (2, 4));
+        assert_eq!(
+            &source[location.range.clone()],
+            "parse_a =\n    one\n    .label = two"
+        );
+        let label = a.locate("label").unwrap();
+        assert_eq!(label.lines, (4, 4));
+        assert_eq!(&source[label.range.clone()], ".label = two");
+        assert_eq!(a.locate("parse_a").unwrap().lines, (2, 3));
+        let b = parser.entries[1].location.as_ref().unwrap();
+        assert_eq!(
+            (b.lines, &source[b.range.clone()]),
+            ((6, 6), "parse_b = three")
+        );
+    }
+
+    #[test]
+    fn test_parse_duplicate_message() {
+        let mut parser = Parser::with_path("messages.ftl");
+        let error = parser
+            .parse(
+                "parse_a = one
+    .label = first
+    .note = a note
+    .label = second
+parse_b = {-brand}
+parse_a = two
+-brand = Rust
+-brand = Rustc
+",
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "messages.ftl:4: duplicate attribute `parse_a.label`, first defined on line 2
+messages.ftl:6: duplicate message `parse_a`, first defined on line 1
+messages.ftl:8: duplicate message `-brand`, first defined on line 7"
+        );
+        let entries = &parser.entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            (entries[0].value.as_str(), entries[1].value.as_str()),
+            ("one", "Rust")
+        );
+        assert_eq!(
+            entries[0].childs[0],
+            (".label".to_string(), "first".to_string())
+        );
+        assert_eq!(entries[0].childs.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_errors_and_duplicates() {
+        let mut parser = Parser::with_path("messages.ftl");
+        let error = parser
+            .parse("parse_a = one\nparse_a = two\nparse_bad = {\n")
+            .unwrap_err();
+        let Error::FtlParse {
+            errors, duplicates, ..
+        } = &error
+        else {
+            panic!("unexpected error: {}", error);
+        };
+        assert_eq!((errors.len(), duplicates.len()), (1, 1));
+        assert_eq!(
+            error.to_string(),
+            "messages.ftl:2: duplicate message `parse_a`, first defined on line 1
+messages.ftl:4:1: Expected an inline expression"
+        );
     }
 }
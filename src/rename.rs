@@ -0,0 +1,304 @@
+//! Rename a message slug in a FTL file and in every Rust source which uses
+//! it.
+//!
+//! The FTL file keeps its layout, only the name of the message and the
+//! references to it and to its attributes change. In the sources, the slugs of
+//! the diagnostic attributes and the `fluent::` paths are found the same way
+//! the conversion finds them.
+use crate::error::Error;
+use crate::gen::{write_output, FileSummary, WriteOptions};
+use crate::parser::Parser;
+use crate::resolve;
+use crate::rewrite::Rewriter;
+use crate::utils::{
+    collect_rust_files, is_diagnostic_source, line_col, slice_offset, unified_diff,
+};
+use crate::visitor::{AttrSlug, SynVisitor, MESSAGE_ATTRS};
+use fluent_syntax::ast;
+use std::fs;
+use std::path::PathBuf;
+
+/// Whether `name` can be the slug of a message, which rustc turns into a
+/// Rust identifier.
+fn is_slug(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn overlapping(path: &str, source: &str, offset: usize) -> Error {
+    let (line, column) = line_col(source, offset);
+    Error::OverlappingEdit {
+        path: path.to_string(),
+        line,
+        column,
+    }
+}
+
+/// The FTL `source` at `path` with the message `old` renamed to `new`, and
+/// every reference to the message or its attributes.
+pub fn rename_ftl(path: &str, source: &str, old: &str, new: &str) -> Result<String, Error> {
+    let mut parser = Parser::with_path(path);
+    parser.parse(source)?;
+    if !parser.entries.iter().any(|e| e.slug == old) {
+        return Err(Error::UnresolvedSlug {
+            path: path.to_string(),
+            slug: old.to_string(),
+        });
+    }
+    if parser.entries.iter().any(|e| e.slug == new) {
+        return Err(Error::SlugExists {
+            path: path.to_string(),
+            slug: new.to_string(),
+        });
+    }
+
+    let resource = match fluent_syntax::parser::parse(source) {
+        Ok(resource) => resource,
+        Err((resource, _)) => resource,
+    };
+    let mut rewriter = Rewriter::new();
+    let mut rename = |name: &str| {
+        let start = slice_offset(source, name);
+        rewriter.replace(start..start + name.len(), new.to_string());
+    };
+    for entry in resource.body.iter() {
+        let (value, attributes) = match entry {
+            ast::Entry::Message(message) => {
+                if message.id.name == old {
+                    rename(message.id.name);
+                }
+                (message.value.as_ref(), &message.attributes)
+            }
+            ast::Entry::Term(term) => (Some(&term.value), &term.attributes),
+            _ => continue,
+        };
+        let patterns = value.into_iter().chain(attributes.iter().map(|a| &a.value));
+        for reference in patterns.flat_map(resolve::references) {
+            if let ast::InlineExpression::MessageReference { id, .. } = reference {
+                if id.name == old {
+                    rename(id.name);
+                }
+            }
+        }
+    }
+    rewriter
+        .apply(source)
+        .map_err(|offset| overlapping(path, source, offset))
+}
+
+/// The Rust `code` at `path` with the slug `old` renamed to `new` in the
+/// diagnostic attributes and the `fluent::` paths.
+pub fn rename_source(
+    path: &str,
+    code: &str,
+    old: &str,
+    new: &str,
+) -> Result<(String, FileSummary), Error> {
+    let syntax = syn::parse_file(code).map_err(|e| Error::rust_parse(path, &e))?;
+    let mut visitor = SynVisitor::new(path, code);
+    visitor.init_with_syntax(&syntax);
+
+    let mut rewriter = Rewriter::new();
+    let mut diagnostics = 0;
+    for attrs in visitor.attrs.values() {
+        let mut renamed = false;
+        for attr in attrs.iter() {
+            if !MESSAGE_ATTRS.iter().any(|key| attr.path().is_ident(key)) {
+                continue;
+            }
+            if let Some(AttrSlug::Slug { name, range, .. }) = AttrSlug::from_attr(attr) {
+                if name == old {
+                    rewriter.replace(range, new.to_string());
+                    renamed = true;
+                }
+            }
+        }
+        diagnostics += renamed as usize;
+    }
+    let mut fluent_paths = 0;
+    for (source, range) in visitor.path_replace.iter() {
+        if source.split("::").collect::<Vec<_>>() == ["fluent", old] {
+            rewriter.replace(range.clone(), format!("fluent::{}", new));
+            fluent_paths += 1;
+        }
+    }
+
+    let result = rewriter
+        .apply(code)
+        .map_err(|offset| overlapping(path, code, offset))?;
+    let summary = FileSummary {
+        path: path.to_string(),
+        diagnostics,
+        fluent_paths,
+        changed: result != code,
+        ..Default::default()
+    };
+    Ok((result, summary))
+}
+
+/// Rename the message `old` of `ftl_file` to `new`, and its uses in
+/// `sources`, a directory stands for its diagnostic sources. Every file is
+/// renamed before any is written, so that nothing is written when one of
+/// them fails.
+pub fn rename(
+    ftl_file: &str,
+    sources: &[PathBuf],
+    old: &str,
+    new: &str,
+    options: &WriteOptions,
+) -> Result<Vec<FileSummary>, Error> {
+    if !is_slug(new) {
+        return Err(Error::Usage(format!("`{}` is not a valid slug", new)));
+    }
+    let ftl = fs::read_to_string(ftl_file).map_err(|e| Error::io(ftl_file, e))?;
+    let renamed = rename_ftl(ftl_file, &ftl, old, new)?;
+    let mut summaries = vec![FileSummary {
+        path: ftl_file.to_string(),
+        changed: renamed != ftl,
+        dry_run: options.read_only(),
+        ..Default::default()
+    }];
+    let mut files = vec![(PathBuf::from(ftl_file), renamed, ftl)];
+    let mut paths = vec![];
+    for source in sources.iter() {
+        match source.is_dir() {
+            true => paths.extend(collect_rust_files(source)?),
+            false => paths.push(source.clone()),
+        }
+    }
+    for path in paths {
+        let code = fs::read_to_string(&path).map_err(|e| Error::io(path.display(), e))?;
+        // the files of a directory are renamed only when they have diagnostics
+        if !sources.contains(&path) && !is_diagnostic_source(&code) {
+            continue;
+        }
+        let (result, mut summary) = rename_source(&path.display().to_string(), &code, old, new)?;
+        summary.dry_run = options.read_only();
+        files.push((path, result, code));
+        summaries.push(summary);
+    }
+
+    for ((path, result, before), summary) in files.iter().zip(summaries.iter_mut()) {
+        if result == before {
+            continue;
+        }
+        if options.diff {
+            summary.output = Some(unified_diff(&summary.path, before, result));
+        } else if !options.dry_run {
+            write_output(path, path, result, options)?;
+        }
+    }
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FTL: &str = "parse_maybe_report_ambiguous_plus = ambiguous `+` in a type
+    .suggestion = use parentheses to disambiguate
+
+# refers to the message above
+parse_maybe_report_ambiguous_plus_note = {parse_maybe_report_ambiguous_plus}: {parse_maybe_report_ambiguous_plus.suggestion}
+    .label = {$n ->
+        [one] {parse_maybe_report_ambiguous_plus}
+       *[other] {NUMBER($n)}
+    }
+";
+
+    #[test]
+    fn test_rename_ftl() {
+        let result = rename_ftl(
+            "messages.ftl",
+            FTL,
+            "parse_maybe_report_ambiguous_plus",
+            "parse_ambiguous_plus",
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "parse_ambiguous_plus = ambiguous `+` in a type
+    .suggestion = use parentheses to disambiguate
+
+# refers to the message above
+parse_maybe_report_ambiguous_plus_note = {parse_ambiguous_plus}: {parse_ambiguous_plus.suggestion}
+    .label = {$n ->
+        [one] {parse_ambiguous_plus}
+       *[other] {NUMBER($n)}
+    }
+"
+        );
+
+        let error = rename_ftl(
+            "messages.ftl",
+            FTL,
+            "parse_maybe_report_ambiguous_plus",
+            "parse_maybe_report_ambiguous_plus_note",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "messages.ftl: message `parse_maybe_report_ambiguous_plus_note` already exists"
+        );
+        let error = rename_ftl("messages.ftl", FTL, "parse_missing", "parse_new").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "messages.ftl: no message found for `parse_missing`"
+        );
+    }
+
+    #[test]
+    fn test_rename_source() {
+        let code = r#"#[derive(Diagnostic)]
+#[diag(parse_maybe_report_ambiguous_plus)]
+pub(crate) struct AmbiguousPlus {
+    #[suggestion(code = "({sum_ty})")]
+    pub span: Span,
+}
+
+#[derive(Subdiagnostic)]
+pub(crate) enum Sub {
+    #[label(parse_maybe_report_ambiguous_plus, style = "short")]
+    Label,
+    #[label(parse_maybe_report_ambiguous_plus_note)]
+    Note,
+}
+
+fn f() {
+    err.note(fluent::parse_maybe_report_ambiguous_plus);
+    err.note(fluent::parse_maybe_report_ambiguous_plus_note);
+}
+"#;
+        let (result, summary) = rename_source(
+            "errors.rs",
+            code,
+            "parse_maybe_report_ambiguous_plus",
+            "parse_ambiguous_plus",
+        )
+        .unwrap();
+        let expected = code
+            .replace(
+                "(parse_maybe_report_ambiguous_plus)",
+                "(parse_ambiguous_plus)",
+            )
+            .replace(
+                "(parse_maybe_report_ambiguous_plus,",
+                "(parse_ambiguous_plus,",
+            )
+            .replace(
+                "fluent::parse_maybe_report_ambiguous_plus)",
+                "fluent::parse_ambiguous_plus)",
+            );
+        assert_eq!(result, expected);
+        assert_eq!((summary.diagnostics, summary.fluent_paths), (2, 1));
+    }
+
+    #[test]
+    fn test_is_slug() {
+        assert!(is_slug("parse_ambiguous_plus2"));
+        assert!(!is_slug("parse-ambiguous"));
+        assert!(!is_slug("2parse"));
+        assert!(!is_slug(""));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::entry::Location;
+use serde_json::{json, Value};
+
+/// How one message attribute, or one `fluent::` path, was resolved.
+#[derive(Debug, Clone)]
+pub struct AttrRecord {
+    /// The attribute name, `fluent` for a path.
+    pub attr: String,
+    /// The slug which was looked up, `.label` for attributes without a slug.
+    pub slug: String,
+    /// The inlined message, or why it could not be resolved.
+    pub message: Result<String, String>,
+    pub line: usize,
+    pub column: usize,
+    /// Where the message is defined in the FTL file.
+    pub definition: Option<Location>,
+}
+
+impl AttrRecord {
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "attribute": self.attr,
+            "slug": self.slug,
+            "line": self.line,
+            "column": self.column,
+        });
+        match &self.message {
+            Ok(message) => value["message"] = json!(message),
+            Err(reason) => value["error"] = json!(reason),
+        }
+        if let Some(definition) = &self.definition {
+            value["definition"] = definition.to_json();
+        }
+        value
+    }
+}
+
+/// A diagnostic struct, enum or variant with the resolution of its attributes.
+#[derive(Debug, Clone)]
+pub struct DiagRecord {
+    pub name: String,
+    pub slug: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub attrs: Vec<AttrRecord>,
+}
+
+impl DiagRecord {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "slug": self.slug,
+            "line": self.line,
+            "column": self.column,
+            "attributes": self.attrs.iter().map(|a| a.to_json()).collect::<Vec<_>>(),
+        })
+    }
+}
@@ -0,0 +1,364 @@
+use crate::utils::{dedent_pattern, fluent_variables, line_col, slice_offset};
+use fluent_syntax::ast;
+use fluent_syntax::unicode::unescape_unicode;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Where a pattern is in the source, `start` is just after its `=`.
+#[derive(Clone, Copy)]
+pub struct PatternSource<'s> {
+    pub pattern: &'s ast::Pattern<&'s str>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Resolves the text of the patterns of a resource, expanding string literals
+/// and references to messages, terms and their attributes.
+///
+/// Patterns are keyed by `name`, `name.attr`, `-term` and `-term.attr`. A
+/// pattern which can not be resolved keeps its text and gets the reason.
+pub struct Resolver<'s> {
+    source: &'s str,
+    path: &'s str,
+    patterns: HashMap<String, PatternSource<'s>>,
+    resolved: HashMap<String, (String, Option<String>)>,
+    stack: Vec<String>,
+}
+
+impl<'s> Resolver<'s> {
+    pub fn new(source: &'s str, path: &'s str) -> Self {
+        Self {
+            source,
+            path,
+            patterns: HashMap::new(),
+            resolved: HashMap::new(),
+            stack: vec![],
+        }
+    }
+
+    /// Register the pattern `key`, the first pattern of a key is kept.
+    pub fn add(&mut self, key: String, pattern: PatternSource<'s>) {
+        self.patterns.entry(key).or_insert(pattern);
+    }
+
+    /// The text of the pattern `key`, with why it needs a manual conversion.
+    pub fn text(&mut self, key: &str) -> (String, Option<String>) {
+        if let Some(resolved) = self.resolved.get(key) {
+            return resolved.clone();
+        }
+        let Some(pattern) = self.patterns.get(key).copied() else {
+            return (String::new(), None);
+        };
+        self.stack.push(key.to_string());
+        let mut edits = vec![];
+        let mut error = self.check_pattern(key, pattern.pattern);
+        self.placeable_edits(key, pattern.pattern, &mut edits, &mut error);
+        self.stack.pop();
+
+        edits.sort_by_key(|(range, _)| range.start);
+        let mut text = String::new();
+        let mut copied = pattern.start;
+        for (range, replacement) in edits.iter() {
+            text.push_str(&self.source[copied..range.start]);
+            text.push_str(replacement);
+            copied = range.end;
+        }
+        text.push_str(&self.source[copied..pattern.end]);
+        let resolved = (dedent_pattern(&text), error);
+        self.resolved.insert(key.to_string(), resolved.clone());
+        resolved
+    }
+
+    fn location(&self, offset: usize) -> String {
+        let (line, column) = line_col(self.source, offset);
+        format!("{}:{}:{}", self.path, line, column)
+    }
+
+    /// Why a pattern can not be inlined as it is. A select expression on a
+    /// variable stays valid Fluent inside a single string, rustc can not
+    /// evaluate a select on anything else.
+    fn check_pattern(&self, name: &str, pattern: &ast::Pattern<&str>) -> Option<String> {
+        pattern.elements.iter().find_map(|element| match element {
+            ast::PatternElement::Placeable { expression } => {
+                self.check_expression(name, expression)
+            }
+            ast::PatternElement::TextElement { .. } => None,
+        })
+    }
+
+    fn check_expression(&self, name: &str, expression: &ast::Expression<&str>) -> Option<String> {
+        match expression {
+            ast::Expression::Select { selector, variants } => {
+                if !matches!(selector, ast::InlineExpression::VariableReference { .. }) {
+                    let (text, anchor) = inline_text(selector);
+                    return Some(format!(
+                        "`{}` selects on `{}` at {} and needs manual conversion",
+                        name,
+                        text,
+                        self.location(slice_offset(self.source, anchor))
+                    ));
+                }
+                variants
+                    .iter()
+                    .find_map(|v| self.check_pattern(name, &v.value))
+            }
+            ast::Expression::Inline(ast::InlineExpression::Placeable { expression }) => {
+                self.check_expression(name, expression)
+            }
+            ast::Expression::Inline(_) => None,
+        }
+    }
+
+    /// Replace the string literal and reference placeables of a pattern, the
+    /// first problem is kept in `error`.
+    fn placeable_edits(
+        &mut self,
+        name: &str,
+        pattern: &ast::Pattern<&str>,
+        edits: &mut Vec<(Range<usize>, String)>,
+        error: &mut Option<String>,
+    ) {
+        let last = pattern.elements.len().saturating_sub(1);
+        for (index, element) in pattern.elements.iter().enumerate() {
+            let ast::PatternElement::Placeable { expression } = element else {
+                continue;
+            };
+            let inline = match expression {
+                ast::Expression::Inline(inline) => inline,
+                ast::Expression::Select { variants, .. } => {
+                    for variant in variants.iter() {
+                        self.placeable_edits(name, &variant.value, edits, error);
+                    }
+                    continue;
+                }
+            };
+            let edit = match inline {
+                ast::InlineExpression::StringLiteral { value } => {
+                    Some(self.literal_edit(value, index == 0, index == last))
+                }
+                ast::InlineExpression::MessageReference { .. }
+                | ast::InlineExpression::TermReference { .. } => {
+                    match self.reference_edit(name, inline) {
+                        Ok(edit) => Some(edit),
+                        Err(reason) => {
+                            error.get_or_insert(reason);
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+            edits.extend(edit);
+        }
+    }
+
+    /// The range of the placeable `{ .. }` around the source from `start` to
+    /// `end`.
+    fn placeable_range(&self, start: usize, end: usize) -> Range<usize> {
+        let open = self.source[..start].rfind('{').unwrap();
+        let close = end + self.source[end..].find('}').unwrap();
+        open..close + 1
+    }
+
+    /// The offset after the `)` which closes the arguments of a call opened
+    /// after `start`. String literals are skipped, as they may hold a brace or
+    /// a parenthesis.
+    fn arguments_end(&self, start: usize) -> usize {
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        for (index, c) in self.source[start..].char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                _ if in_string => {}
+                '(' => depth += 1,
+                ')' if depth == 1 => return start + index + 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+        self.source.len()
+    }
+
+    /// Resolve a string literal placeable such as `{"a"}` into plain text
+    /// where it means the same. A brace, white space at the edges of a
+    /// pattern and `[`, `*` or `.` at the start of a line can only be written
+    /// as a placeable, those are kept as `{"{"}`.
+    fn literal_edit(&self, value: &str, first: bool, last: bool) -> (Range<usize>, String) {
+        let quote = slice_offset(self.source, value) - 1;
+        let range = self.placeable_range(quote, quote + value.len() + 2);
+        let line_start = self.source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let at_line_start = self.source[line_start..range.start].trim().is_empty();
+
+        let mut text = String::new();
+        unescape_unicode(&mut text, value).unwrap();
+        let needs_placeable = text.contains(['{', '}'])
+            || text.chars().any(char::is_control)
+            || (first && text.starts_with(char::is_whitespace))
+            || (last && text.ends_with(char::is_whitespace))
+            || (at_line_start
+                && text.starts_with(|c: char| c.is_whitespace() || "[*.".contains(c)));
+        if needs_placeable {
+            (range, format!("{{\"{}\"}}", escape_literal(&text)))
+        } else {
+            (range, text)
+        }
+    }
+
+    /// Expand a reference to a message, a term or one of their attributes
+    /// with the resolved text of its pattern.
+    fn reference_edit(
+        &mut self,
+        name: &str,
+        reference: &ast::InlineExpression<&str>,
+    ) -> Result<(Range<usize>, String), String> {
+        let (target, id, attribute, arguments) = match reference {
+            ast::InlineExpression::MessageReference { id, attribute } => {
+                (id.name.to_string(), id, attribute, None)
+            }
+            ast::InlineExpression::TermReference {
+                id,
+                attribute,
+                arguments,
+            } => (format!("-{}", id.name), id, attribute, arguments.as_ref()),
+            _ => unreachable!("not a reference"),
+        };
+        let target = match attribute {
+            Some(attr) => format!("{}.{}", target, attr.name),
+            None => target,
+        };
+        let start = slice_offset(self.source, id.name);
+        let mut end = match attribute {
+            Some(attr) => slice_offset(self.source, attr.name) + attr.name.len(),
+            None => start + id.name.len(),
+        };
+        if arguments.is_some() {
+            end = self.arguments_end(end);
+        }
+        let range = self.placeable_range(start, end);
+        let location = self.location(range.start);
+
+        if !self.patterns.contains_key(&target) {
+            return Err(format!(
+                "`{}` refers to `{}` at {}, which is not defined",
+                name, target, location
+            ));
+        }
+        if let Some(index) = self.stack.iter().position(|key| key == &target) {
+            let mut cycle = self.stack[index..].to_vec();
+            cycle.push(target);
+            return Err(format!(
+                "reference cycle `{}` at {}",
+                cycle.join(" -> "),
+                location
+            ));
+        }
+        let (text, error) = self.text(&target);
+        if let Some(error) = error {
+            return Err(error);
+        }
+        if arguments.is_some() && !fluent_variables(&text).is_empty() {
+            return Err(format!(
+                "`{}` passes arguments to `{}` at {} and needs manual conversion",
+                name, target, location
+            ));
+        }
+        Ok((range, text))
+    }
+}
+
+/// A short form of an inline expression, with a slice of the source which
+/// points into it.
+fn inline_text<'s>(expression: &ast::InlineExpression<&'s str>) -> (String, &'s str) {
+    match expression {
+        ast::InlineExpression::StringLiteral { value } => (format!("\"{}\"", value), value),
+        ast::InlineExpression::NumberLiteral { value } => (value.to_string(), value),
+        ast::InlineExpression::FunctionReference { id, .. } => {
+            (format!("{}(..)", id.name), id.name)
+        }
+        ast::InlineExpression::MessageReference { id, attribute } => match attribute {
+            Some(attr) => (format!("{}.{}", id.name, attr.name), id.name),
+            None => (id.name.to_string(), id.name),
+        },
+        ast::InlineExpression::TermReference { id, attribute, .. } => match attribute {
+            Some(attr) => (format!("-{}.{}", id.name, attr.name), id.name),
+            None => (format!("-{}", id.name), id.name),
+        },
+        ast::InlineExpression::VariableReference { id } => (format!("${}", id.name), id.name),
+        ast::InlineExpression::Placeable { expression } => match expression.as_ref() {
+            ast::Expression::Select { selector, .. } => inline_text(selector),
+            ast::Expression::Inline(inline) => inline_text(inline),
+        },
+    }
+}
+
+/// The message and term references of a pattern, in select expressions and
+/// in the arguments of calls too.
+pub(crate) fn references<'a, 's>(
+    pattern: &'a ast::Pattern<&'s str>,
+) -> Vec<&'a ast::InlineExpression<&'s str>> {
+    let mut references = vec![];
+    for element in pattern.elements.iter() {
+        if let ast::PatternElement::Placeable { expression } = element {
+            expression_references(expression, &mut references);
+        }
+    }
+    references
+}
+
+fn expression_references<'a, 's>(
+    expression: &'a ast::Expression<&'s str>,
+    references: &mut Vec<&'a ast::InlineExpression<&'s str>>,
+) {
+    match expression {
+        ast::Expression::Select { selector, variants } => {
+            inline_references(selector, references);
+            for variant in variants.iter() {
+                references.extend(self::references(&variant.value));
+            }
+        }
+        ast::Expression::Inline(inline) => inline_references(inline, references),
+    }
+}
+
+fn inline_references<'a, 's>(
+    inline: &'a ast::InlineExpression<&'s str>,
+    references: &mut Vec<&'a ast::InlineExpression<&'s str>>,
+) {
+    let arguments = match inline {
+        ast::InlineExpression::MessageReference { .. } => {
+            return references.push(inline);
+        }
+        ast::InlineExpression::TermReference { arguments, .. } => {
+            references.push(inline);
+            arguments.as_ref()
+        }
+        ast::InlineExpression::FunctionReference { arguments, .. } => Some(arguments),
+        ast::InlineExpression::Placeable { expression } => {
+            return expression_references(expression, references);
+        }
+        _ => None,
+    };
+    for argument in arguments.into_iter() {
+        let named = argument.named.iter().map(|n| &n.value);
+        for value in argument.positional.iter().chain(named) {
+            inline_references(value, references);
+        }
+    }
+}
+
+/// Encode `text` as the content of a Fluent string literal.
+fn escape_literal(text: &str) -> String {
+    let mut literal = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04X}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal
+}
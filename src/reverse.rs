@@ -0,0 +1,642 @@
+//! Extract inline messages back into FTL entries, the inverse of the
+//! conversion.
+//!
+//! `#[diag("..")]` and the other message attributes get a slug named after
+//! their struct or variant with the prefix of the crate, `rustc_parse` uses
+//! `parse_`. The other messages of a diagnostic become attributes of its entry
+//! when rustc can find them there, `#[label("..")]` turns into `#[label]`.
+//! `DiagnosticMessage::Str(Cow::from(".."))` becomes a `fluent::` path with a
+//! slug made of the first words of the message.
+use crate::catalog::Catalog;
+use crate::entry::Entry;
+use crate::error::Error;
+use crate::gen::{parse_ftl, write_output, FileSummary, WriteOptions};
+use crate::rewrite::Rewriter;
+use crate::utils::{collect_rust_files, is_diagnostic_source, line_col, unified_diff};
+use crate::visitor::{
+    default_fluent_attr, is_suggestion, AttrSlug, ITEM_SLUG_ATTRS, MESSAGE_ATTRS,
+};
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Attribute, Meta, Token};
+
+/// How many words of a message make the slug of a `fluent::` path.
+const SLUG_WORDS: usize = 6;
+
+/// An argument of a message attribute, the message itself is a string
+/// literal.
+enum Arg {
+    Message(syn::LitStr),
+    Meta(Box<Meta>),
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::LitStr) {
+            input.parse().map(Arg::Message)
+        } else {
+            input.parse().map(|meta| Arg::Meta(Box::new(meta)))
+        }
+    }
+}
+
+/// A message attribute, with its message when it is written inline.
+struct Message {
+    attr: &'static str,
+    inline: Option<InlineMessage>,
+}
+
+/// `#[label("..")]` or `#[suggestion(label = "..", code = "..")]`.
+struct InlineMessage {
+    text: String,
+    /// The bytes which the slug replaces.
+    range: Range<usize>,
+    /// The bytes to remove for the attribute to refer to the Fluent attribute
+    /// of the same name, such as `#[label]`.
+    bare: Range<usize>,
+}
+
+impl Message {
+    fn from_attr(attr: &Attribute) -> Option<Self> {
+        let name = MESSAGE_ATTRS.iter().find(|key| attr.path().is_ident(key))?;
+        let Meta::List(list) = &attr.meta else {
+            return Some(Message {
+                attr: name,
+                inline: None,
+            });
+        };
+        let args = list
+            .parse_args_with(Punctuated::<Arg, Token![,]>::parse_terminated)
+            .ok()?;
+        let ranges = args
+            .iter()
+            .map(|arg| match arg {
+                Arg::Message(lit) => lit.span().byte_range(),
+                Arg::Meta(meta) => meta.span().byte_range(),
+            })
+            .collect::<Vec<_>>();
+        let inline = args.iter().enumerate().find_map(|(index, arg)| {
+            let text = match arg {
+                Arg::Message(lit) => lit.value(),
+                Arg::Meta(meta) if meta.path().is_ident("label") => match meta.as_ref() {
+                    Meta::NameValue(nv) => match &nv.value {
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(lit),
+                            ..
+                        }) => lit.value(),
+                        _ => return None,
+                    },
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            let range = ranges[index].clone();
+            let bare = if let Some(next) = ranges.get(index + 1) {
+                range.start..next.start
+            } else if index > 0 {
+                ranges[index - 1].end..range.end
+            } else if matches!(arg, Arg::Message(_)) {
+                list.delimiter.span().join().byte_range()
+            } else {
+                range.clone()
+            };
+            Some(InlineMessage { text, range, bare })
+        });
+        Some(Message { attr: name, inline })
+    }
+}
+
+/// A struct, enum or variant with its message attributes, including the
+/// ones of its fields.
+struct Item {
+    name: String,
+    messages: Vec<Message>,
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    items: Vec<Item>,
+    /// `DiagnosticMessage::Str(Cow::from(".."))` expressions.
+    paths: Vec<(Range<usize>, String)>,
+    /// The slugs the source already uses, which new entries must not take.
+    slugs: HashSet<String>,
+}
+
+impl MessageVisitor {
+    fn add_item(&mut self, name: &syn::Ident, attrs: &[Attribute], fields: &syn::Fields) {
+        let field_attrs = fields.iter().flat_map(|f| f.attrs.iter());
+        let attrs = attrs.iter().chain(field_attrs).collect::<Vec<_>>();
+        for attr in attrs.iter() {
+            if !MESSAGE_ATTRS.iter().any(|key| attr.path().is_ident(key)) {
+                continue;
+            }
+            if let Some(AttrSlug::Slug { name, .. }) = AttrSlug::from_attr(attr) {
+                self.slugs.insert(name);
+            }
+        }
+        let messages = attrs
+            .into_iter()
+            .filter_map(Message::from_attr)
+            .collect::<Vec<_>>();
+        if messages.iter().any(|m| m.inline.is_some()) {
+            self.items.push(Item {
+                name: name.to_string(),
+                messages,
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for MessageVisitor {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.add_item(&i.ident, &i.attrs, &i.fields);
+        visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        self.add_item(&i.ident, &i.attrs, &syn::Fields::Unit);
+        visit::visit_item_enum(self, i);
+    }
+
+    fn visit_variant(&mut self, i: &'ast syn::Variant) {
+        self.add_item(&i.ident, &i.attrs, &i.fields);
+        visit::visit_variant(self, i);
+    }
+
+    fn visit_expr_call(&mut self, i: &'ast syn::ExprCall) {
+        match diagnostic_str(i) {
+            Some(text) => self.paths.push((i.span().byte_range(), text)),
+            None => visit::visit_expr_call(self, i),
+        }
+    }
+
+    fn visit_path(&mut self, i: &'ast syn::Path) {
+        let segments = i.segments.iter().rev().take(2).collect::<Vec<_>>();
+        if segments.len() == 2 && segments[1].ident == "fluent" {
+            self.slugs.insert(segments[0].ident.to_string());
+        }
+        visit::visit_path(self, i);
+    }
+}
+
+/// The message of `DiagnosticMessage::Str(Cow::from(".."))`.
+fn diagnostic_str(call: &syn::ExprCall) -> Option<String> {
+    let is_call_of = |func: &syn::Expr, names: [&str; 2]| match func {
+        syn::Expr::Path(path) => {
+            let segments = path.path.segments.iter().rev().take(2).collect::<Vec<_>>();
+            segments.len() == 2 && segments[0].ident == names[1] && segments[1].ident == names[0]
+        }
+        _ => false,
+    };
+    if !is_call_of(&call.func, ["DiagnosticMessage", "Str"]) || call.args.len() != 1 {
+        return None;
+    }
+    let syn::Expr::Call(cow) = &call.args[0] else {
+        return None;
+    };
+    if !is_call_of(&cow.func, ["Cow", "from"]) || cow.args.len() != 1 {
+        return None;
+    }
+    match &cow.args[0] {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => Some(lit.value()),
+        _ => None,
+    }
+}
+
+/// `AmbiguousPlus` as `ambiguous_plus`.
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev = index.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(index + 1);
+            let boundary = prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(char::is_uppercase) && next.is_some_and(|n| n.is_lowercase()));
+            if boundary {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(*c);
+        }
+    }
+    snake
+}
+
+/// The crate prefix of slugs, `parse` for `compiler/rustc_parse`.
+pub fn crate_prefix(crate_root: &Path) -> Option<String> {
+    let name = crate_root.file_name()?.to_string_lossy();
+    Some(name.strip_prefix("rustc_").unwrap_or(&name).to_string())
+}
+
+/// Collects the entries of the inline messages of the sources of one crate,
+/// against the entries its FTL file already has.
+pub struct Extractor {
+    prefix: String,
+    /// The entries of the FTL file, then the new ones.
+    entries: Vec<Entry>,
+    /// How many of `entries` are new.
+    added: usize,
+    /// The slugs the source being extracted already uses.
+    used: HashSet<String>,
+}
+
+impl Extractor {
+    pub fn new(prefix: &str, entries: &[Entry]) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            entries: entries.to_vec(),
+            added: 0,
+            used: HashSet::new(),
+        }
+    }
+
+    /// The new entries, in the order they were found.
+    pub fn new_entries(&self) -> &[Entry] {
+        &self.entries[self.entries.len() - self.added..]
+    }
+
+    /// The FTL source of the new entries, separated by blank lines.
+    #[cfg(test)]
+    pub fn new_ftl(&self) -> String {
+        let mut catalog = Catalog::default();
+        for entry in self.new_entries() {
+            catalog.push(entry.into());
+        }
+        catalog.to_string()
+    }
+
+    /// The slug for `entry`, `base` or the first of `base_2`, `base_3`, ..
+    /// which is free or already has the same messages. A slug the source uses
+    /// without an entry is not free.
+    fn claim(&mut self, base: &str, mut entry: Entry) -> String {
+        for n in 1.. {
+            let slug = match n {
+                1 => base.to_string(),
+                n => format!("{}_{}", base, n),
+            };
+            match self.entries.iter().find(|e| e.slug == slug) {
+                None if self.used.contains(&slug) => {}
+                None => {
+                    entry.slug = slug.clone();
+                    self.entries.push(entry);
+                    self.added += 1;
+                    return slug;
+                }
+                Some(known) if covers(known, &entry) => return slug,
+                Some(_) => {}
+            }
+        }
+        unreachable!()
+    }
+
+    /// Rewrite the inline messages of `code` back to slugs.
+    pub fn extract(&mut self, path: &str, code: &str) -> Result<(String, FileSummary), Error> {
+        let syntax = syn::parse_file(code).map_err(|e| Error::rust_parse(path, &e))?;
+        let mut visitor = MessageVisitor::default();
+        visitor.visit_file(&syntax);
+        self.used = visitor.slugs;
+
+        let mut rewriter = Rewriter::new();
+        for item in visitor.items.iter() {
+            self.extract_item(item, &mut rewriter);
+        }
+        for (range, text) in visitor.paths.iter() {
+            let words = text
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .take(SLUG_WORDS)
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>();
+            let words = if words.is_empty() {
+                "message".to_string()
+            } else {
+                words.join("_")
+            };
+            let base = format!("{}_{}", self.prefix, words);
+            let slug = self.claim(&base, Entry::new(String::new(), text.clone()));
+            rewriter.replace(range.clone(), format!("fluent::{}", slug));
+        }
+
+        let result = rewriter.apply(code).map_err(|offset| {
+            let (line, column) = line_col(code, offset);
+            Error::OverlappingEdit {
+                path: path.to_string(),
+                line,
+                column,
+            }
+        })?;
+        let summary = FileSummary {
+            path: path.to_string(),
+            diagnostics: visitor.items.len(),
+            fluent_paths: visitor.paths.len(),
+            changed: result != code,
+            ..Default::default()
+        };
+        Ok((result, summary))
+    }
+
+    /// The first message of a diagnostic is the value of its entry. When
+    /// rustc takes the slug of the diagnostic from it, the other messages
+    /// become attributes of the entry, unless an attribute of the same name
+    /// has another message, otherwise they get entries of their own.
+    fn extract_item(&mut self, item: &Item, rewriter: &mut Rewriter) {
+        let mut base = format!("{}_{}", self.prefix, snake_case(&item.name));
+        let mut own = vec![];
+        let mut rest = &item.messages[..];
+        if let Some(InlineMessage { text, range, .. }) = &item.messages[0].inline {
+            let attr = item.messages[0].attr;
+            let mut entry = Entry::new(String::new(), text.clone());
+            rest = &item.messages[1..];
+            if ITEM_SLUG_ATTRS.contains(&attr) || is_suggestion(attr) {
+                let mut bare = vec![];
+                for message in rest.iter() {
+                    let Some(inline) = &message.inline else {
+                        continue;
+                    };
+                    let key = format!(".{}", default_fluent_attr(message.attr));
+                    match entry.childs.iter().find(|(k, _)| k == &key) {
+                        Some((_, text)) if text != &inline.text => own.push(message),
+                        Some(_) => bare.push(inline.bare.clone()),
+                        None => {
+                            entry.add_child(key, inline.text.clone());
+                            bare.push(inline.bare.clone());
+                        }
+                    }
+                }
+                rest = &[];
+                for range in bare {
+                    rewriter.replace(range, String::new());
+                }
+            }
+            base = self.claim(&base, entry);
+            rewriter.replace(range.clone(), base.clone());
+        }
+        own.extend(rest.iter());
+        for message in own {
+            let Some(inline) = &message.inline else {
+                continue;
+            };
+            let base = format!("{}_{}", base, default_fluent_attr(message.attr));
+            let slug = self.claim(&base, Entry::new(String::new(), inline.text.clone()));
+            rewriter.replace(inline.range.clone(), slug);
+        }
+    }
+}
+
+/// Whether the messages of `entry` are all in `known`, so that it can be
+/// used in its place.
+fn covers(known: &Entry, entry: &Entry) -> bool {
+    known.manual.is_empty()
+        && known.value == entry.value
+        && entry
+            .childs
+            .iter()
+            .all(|child| known.childs.contains(child))
+}
+
+/// Extract the inline messages of `sources` into `ftl_file`, which is
+/// created when it does not exist yet, and rewrite the sources in place.
+/// Every source is extracted before any file is written, so that nothing is
+/// written when one of them fails.
+pub fn reverse_files(
+    ftl_file: &str,
+    prefix: &str,
+    sources: &[PathBuf],
+    options: &WriteOptions,
+) -> Result<Vec<FileSummary>, Error> {
+    let ftl_path = Path::new(ftl_file);
+    let (ftl, entries) = if ftl_path.is_file() {
+        let ftl = fs::read_to_string(ftl_path).map_err(|e| Error::io(ftl_file, e))?;
+        (ftl, parse_ftl(ftl_file)?)
+    } else {
+        (String::new(), vec![])
+    };
+    let mut extractor = Extractor::new(prefix, &entries);
+    let mut files = vec![];
+    let mut summaries = vec![];
+    for source in sources.iter() {
+        let path = source.display().to_string();
+        let code = fs::read_to_string(source).map_err(|e| Error::io(&path, e))?;
+        let (result, mut summary) = extractor.extract(&path, &code)?;
+        summary.dry_run = options.read_only();
+        files.push((source.clone(), result, code));
+        summaries.push(summary);
+    }
+    if extractor.added > 0 {
+        let mut catalog = Catalog::parse(&ftl);
+        for entry in extractor.new_entries() {
+            catalog.push(entry.into());
+        }
+        // the messages go first, so that the slugs of the sources exist
+        let result = catalog.to_string();
+        summaries.insert(
+            0,
+            FileSummary {
+                path: ftl_file.to_string(),
+                changed: result != ftl,
+                dry_run: options.read_only(),
+                ..Default::default()
+            },
+        );
+        files.insert(0, (ftl_path.to_path_buf(), result, ftl));
+    }
+
+    for ((path, result, before), summary) in files.iter().zip(summaries.iter_mut()) {
+        if result == before {
+            continue;
+        }
+        if options.diff {
+            summary.output = Some(unified_diff(&summary.path, before, result));
+        } else if !options.dry_run {
+            write_output(path, path, result, options)?;
+        }
+    }
+    Ok(summaries)
+}
+
+/// Extract the inline messages of every diagnostic source of `crate_root`.
+pub fn reverse_crate(
+    ftl_file: &str,
+    prefix: &str,
+    crate_root: &str,
+    options: &WriteOptions,
+) -> Result<Vec<FileSummary>, Error> {
+    let mut sources = vec![];
+    for path in collect_rust_files(Path::new(crate_root))? {
+        let code = fs::read_to_string(&path).map_err(|e| Error::io(path.display(), e))?;
+        if is_diagnostic_source(&code) || code.contains("DiagnosticMessage::Str") {
+            sources.push(path);
+        }
+    }
+    reverse_files(ftl_file, prefix, &sources, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen::gen_source;
+    use crate::parser::Parser;
+
+    fn parse(ftl: &str) -> Vec<Entry> {
+        let mut parser = Parser::new();
+        parser.parse(ftl).unwrap();
+        parser.entries
+    }
+
+    #[test]
+    fn test_reverse_round_trip() {
+        for path in ["tests/case1/expect.rs", "tests/escapes/expect.rs"] {
+            let code = fs::read_to_string(path).unwrap();
+            let mut extractor = Extractor::new("parse", &[]);
+            let (reversed, summary) = extractor.extract(path, &code).unwrap();
+            assert!(summary.changed);
+            let entries = parse(&extractor.new_ftl());
+            assert_eq!(entries.len(), extractor.new_entries().len());
+            let (result, summary) = gen_source(&entries, path, &reversed).unwrap();
+            // the shared subdiagnostic is left with its slug, which has no
+            // entry of its own
+            let items = summary.warnings.iter().map(|w| w.item.as_str());
+            assert!(items.eq(match path {
+                "tests/case1/expect.rs" => vec!["ComparisonOrShiftInterpretedAsGenericSugg"],
+                _ => vec![],
+            }));
+            // inserting a message does not follow the layout of the attribute
+            let tokens = |code: &str| {
+                code.parse::<proc_macro2::TokenStream>()
+                    .unwrap()
+                    .to_string()
+            };
+            assert_eq!(
+                tokens(&result),
+                tokens(&code),
+                "{} does not round-trip",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_reverse_slugs() {
+        let source = r#"#[derive(Diagnostic)]
+#[diag("expected `{$ty}`", code = "E0178")]
+#[note("see the reference")]
+pub(crate) struct BadQPathStage2 {
+    pub ty: String,
+    #[label("expected here")]
+    pub span: Span,
+    #[label("and here")]
+    pub other: Span,
+    #[suggestion(label = "add `<>`", code = "<{ty}>")]
+    pub sugg: Span,
+}
+
+#[derive(Subdiagnostic)]
+pub(crate) enum WrapType {
+    #[label("expected here")]
+    Label,
+}
+
+fn f() {
+    err.note(DiagnosticMessage::Str(Cow::from("inner doc comments like this (starting with `//!`) can only appear before items")));
+}
+"#;
+        let ftl = "parse_bad_q_path_stage2 = expected `{$ty}`\n";
+        let mut extractor = Extractor::new("parse", &parse(ftl));
+        let (result, _) = extractor.extract("errors.rs", source).unwrap();
+        for line in [
+            "#[diag(parse_bad_q_path_stage2_2, code = \"E0178\")]",
+            "#[note]",
+            "    #[label]\n    pub span",
+            "#[label(parse_bad_q_path_stage2_2_label)]",
+            "#[suggestion(code = \"<{ty}>\")]",
+            "    #[label(parse_label)]\n    Label",
+            "err.note(fluent::parse_inner_doc_comments_like_this_starting);",
+        ] {
+            assert!(result.contains(line), "missing {}", line);
+        }
+        assert_eq!(
+            extractor.new_ftl(),
+            "parse_bad_q_path_stage2_2 = expected `{$ty}`
+    .note = see the reference
+    .label = expected here
+    .suggestion = add `<>`
+
+parse_bad_q_path_stage2_2_label = and here
+
+parse_label = expected here
+
+parse_inner_doc_comments_like_this_starting = inner doc comments like this (starting with `//!`) can only appear before items
+"
+        );
+
+        let merged = format!("{}\n{}", ftl, extractor.new_ftl());
+        let mut extractor = Extractor::new("parse", &parse(&merged));
+        let (again, _) = extractor.extract("errors.rs", source).unwrap();
+        assert_eq!(again, result);
+        assert!(extractor.new_entries().is_empty());
+    }
+
+    #[test]
+    fn test_reverse_used_slugs() {
+        let source = r#"#[derive(Subdiagnostic)]
+pub(crate) enum Sub {
+    #[label("a label")]
+    Label,
+    #[note(parse_label)]
+    Note,
+}
+
+fn f() {
+    err.note(fluent::parse_a_note);
+    err.note(DiagnosticMessage::Str(Cow::from("a note")));
+}
+"#;
+        let mut extractor = Extractor::new("parse", &[]);
+        let (result, _) = extractor.extract("errors.rs", source).unwrap();
+        assert!(result.contains("#[label(parse_label_2)]"));
+        assert!(result.contains("err.note(fluent::parse_a_note_2);"));
+        assert_eq!(
+            extractor.new_ftl(),
+            "parse_label_2 = a label\n\nparse_a_note_2 = a note\n"
+        );
+    }
+
+    #[test]
+    fn test_reverse_crate() {
+        let root = "/tmp/diag-convert-reverse";
+        let _ = fs::remove_dir_all(root);
+        fs::create_dir_all(format!("{}/src", root)).unwrap();
+        let source = "#[derive(Diagnostic)]\n#[diag(\"hello\")]\npub(crate) struct Hello {\n    #[primary_span]\n    pub span: Span,\n}\n";
+        let a = format!("{}/src/a.rs", root);
+        fs::write(&a, source).unwrap();
+        let ftl = format!("{}/messages.ftl", root);
+
+        // a file which fails to parse leaves every other one untouched
+        let broken = format!("{}/src/b.rs", root);
+        fs::write(&broken, "#[derive(Diagnostic)]\nstruct {\n").unwrap();
+        assert!(reverse_crate(&ftl, "parse", root, &WriteOptions::default()).is_err());
+        assert_eq!(fs::read_to_string(&a).unwrap(), source);
+        assert!(!Path::new(&ftl).exists());
+        fs::remove_file(&broken).unwrap();
+
+        let summaries = reverse_crate(&ftl, "parse", root, &WriteOptions::default()).unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].path, ftl);
+        assert!(summaries.iter().all(|s| s.changed && s.output.is_none()));
+        assert!(fs::read_to_string(&a)
+            .unwrap()
+            .contains("#[diag(parse_hello)]"));
+        assert_eq!(fs::read_to_string(&ftl).unwrap(), "parse_hello = hello\n");
+    }
+}
@@ -0,0 +1,86 @@
+use std::ops::Range;
+
+/// Replace the bytes of `range` with `text`, an empty range is an insertion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// Collects byte-range edits of one source and applies them in one pass, so
+/// that every edit only touches the bytes it was recorded for.
+#[derive(Default)]
+pub struct Rewriter {
+    edits: Vec<Edit>,
+}
+
+impl Rewriter {
+    pub fn new() -> Self {
+        Self { edits: Vec::new() }
+    }
+
+    pub fn replace(&mut self, range: Range<usize>, text: String) {
+        self.edits.push(Edit { range, text });
+    }
+
+    pub fn insert(&mut self, offset: usize, text: String) {
+        self.replace(offset..offset, text);
+    }
+
+    /// Apply every edit to `source`. An edit recorded twice is applied once,
+    /// edits which overlap return the offset where the second one starts.
+    pub fn apply(&self, source: &str) -> Result<String, usize> {
+        let mut edits = self.edits.clone();
+        edits.sort_by_key(|e| (e.range.start, e.range.end));
+        edits.dedup();
+
+        let mut output = String::with_capacity(source.len());
+        let mut last: Option<&Edit> = None;
+        for edit in edits.iter() {
+            if let Some(last) = last {
+                let same_insertion = last.range.is_empty() && last.range == edit.range;
+                if edit.range.start < last.range.end || same_insertion {
+                    return Err(edit.range.start);
+                }
+            }
+            let copied = last.map_or(0, |e| e.range.end);
+            output.push_str(&source[copied..edit.range.start]);
+            output.push_str(&edit.text);
+            last = Some(edit);
+        }
+        output.push_str(&source[last.map_or(0, |e| e.range.end)..]);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_edits() {
+        let source = "#[diag(parse_suggestion)] #[label(parse_suggestion_remove)] #[help]";
+        let mut rewriter = Rewriter::new();
+        rewriter.replace(34..57, "\"remove\"".to_string());
+        rewriter.replace(7..23, "\"suggest\"".to_string());
+        rewriter.insert(66, "(\"help\")".to_string());
+        rewriter.insert(66, "(\"help\")".to_string());
+        assert_eq!(
+            rewriter.apply(source).unwrap(),
+            "#[diag(\"suggest\")] #[label(\"remove\")] #[help(\"help\")]"
+        );
+    }
+
+    #[test]
+    fn test_overlapping_edits() {
+        let mut rewriter = Rewriter::new();
+        rewriter.replace(2..6, "a".to_string());
+        rewriter.replace(4..8, "b".to_string());
+        assert_eq!(rewriter.apply("0123456789"), Err(4));
+
+        let mut rewriter = Rewriter::new();
+        rewriter.insert(3, "a".to_string());
+        rewriter.insert(3, "b".to_string());
+        assert_eq!(rewriter.apply("0123456789"), Err(3));
+    }
+}
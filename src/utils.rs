@@ -1,57 +1,135 @@
+use crate::error::Error;
 use regex::Regex;
+use similar::TextDiff;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
 use syn::{Attribute, Meta, MetaList, Path as SynPath, PathSegment, Type};
 
-pub fn replace_slug(content: &str, slug: &str, to: &str) -> String {
-    let mut result = content.to_string();
-    let from = format!("({})", slug);
-    if result.contains(from.as_str()) {
-        result = result.replace(from.as_str(), format!("({})", to).as_str());
-    } else {
-        result = result.replace(
-            format!("{}", slug).as_str(),
-            format!("label = {} ", to).as_str(),
-        );
-    };
+/// Byte offset of `slice` inside `source`, `slice` must be borrowed from `source`.
+pub fn slice_offset(source: &str, slice: &str) -> usize {
+    slice.as_ptr() as usize - source.as_ptr() as usize
+}
+
+/// 1-based line and column of a byte offset.
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
 
-    return result;
+/// Strip the common indentation from the continuation lines of a pattern,
+/// the way Fluent does, and drop the surrounding blank space.
+pub fn dedent_pattern(text: &str) -> String {
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or("").trim();
+    let rest = lines.map(|l| l.trim_end()).collect::<Vec<_>>();
+    let indent = rest
+        .iter()
+        .filter(|l| !l.is_empty())
+        // Fluent only indents with spaces, counting bytes of other
+        // whitespace could slice inside a character
+        .map(|l| l.len() - l.trim_start_matches(' ').len())
+        .min()
+        .unwrap_or(0);
+    let mut result = vec![first];
+    result.extend(
+        rest.iter()
+            .map(|l| if l.is_empty() { *l } else { &l[indent..] }),
+    );
+    result.join("\n").trim_matches('\n').to_string()
+}
+
+/// All `.rs` files below `dir`, sorted, skipping `target` directories.
+pub fn collect_rust_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = vec![];
+    let read_error = |e| Error::io(dir.display(), e);
+    for entry in fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|n| n != "target") {
+                files.extend(collect_rust_files(&path)?);
+            }
+        } else if path.extension().is_some_and(|e| e == "rs") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
 }
 
-pub fn replace_attr_name(content: &str, attr: &str, to: &str) -> String {
-    let mut result = content.to_string();
-    let from = format!("#[{}]", attr);
-    if result.contains(from.as_str()) {
-        result = result.replace(from.as_str(), format!("#[{}({})]", attr, to).as_str());
-    } else {
-        result = result.replace(
-            format!("{}(", attr).as_str(),
-            format!("{}(label = {}, ", attr, to).as_str(),
-        );
-    };
-    return result;
+/// Whether a Rust source defines diagnostics or refers to `fluent::` slugs.
+pub fn is_diagnostic_source(code: &str) -> bool {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r"#\[derive\([^)]*\b(Diagnostic|Subdiagnostic|LintDiagnostic)\b").unwrap()
+    });
+    re.is_match(code) || code.contains("fluent::")
 }
 
-pub fn append_to_string(prev: &str, add: &str) -> String {
-    if prev.is_empty() {
-        return add.to_string();
+/// The names of the Fluent variables `{$name}` referred to by a message,
+/// including the selectors of select expressions, in order of appearance.
+pub fn fluent_variables(text: &str) -> Vec<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"\{\s*\$([A-Za-z][\w-]*)").unwrap());
+    let mut names: Vec<String> = vec![];
+    for cap in re.captures_iter(text) {
+        if !names.iter().any(|n| n == &cap[1]) {
+            names.push(cap[1].to_string());
+        }
     }
-    format!("{}\n{}", prev, add)
+    names
 }
 
-pub(crate) fn check_kv(input: &str) -> Option<(&str, &str)> {
-    let re = Regex::new(r"(?m)^([\._a-zA-Z][_a-zA-Z0-9]*?) = (.*?)$").unwrap();
-    if re.is_match(input) {
-        let caps = re.captures(input).unwrap();
-        let slug = caps.get(1).unwrap().as_str();
-        let value = caps.get(2).unwrap().as_str();
-        Some((slug, value))
-    } else {
-        None
+/// The field names interpolated by the code of a suggestion, either as
+/// `{name}` or as a Fluent variable `{$name}`, `{{` is an escaped brace.
+pub fn code_variables(code: &str) -> Vec<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"\{\$?([A-Za-z_]\w*)\}").unwrap());
+    let code = code.replace("{{", "").replace("}}", "");
+    let mut names: Vec<String> = vec![];
+    for cap in re.captures_iter(&code) {
+        if !names.iter().any(|n| n == &cap[1]) {
+            names.push(cap[1].to_string());
+        }
     }
+    names
+}
+
+/// `path` as it is written in the headers of a diff, relative to the current
+/// directory, or to the root for an absolute path outside of it.
+fn diff_path(path: &str) -> String {
+    let path = Path::new(path);
+    let cwd = env::current_dir().unwrap_or_default();
+    let relative = path.strip_prefix(&cwd).unwrap_or(path);
+    relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            Component::ParentDir => Some("..".into()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A unified diff of `path` from `from` to `to`, with `a/` and `b/` headers
+/// so that it applies with `git apply` and `patch -p1`.
+pub fn unified_diff(path: &str, from: &str, to: &str) -> String {
+    let path = diff_path(path);
+    TextDiff::from_lines(from, to)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
 }
 
 pub fn get_path_first(path: &SynPath) -> String {
     let first_segment = path.segments.first().unwrap();
-    return first_segment.ident.to_string();
+    first_segment.ident.to_string()
 }
 
 pub fn get_diag_type(attr: &Attribute) -> Option<String> {
@@ -68,30 +146,26 @@ pub fn get_diag_type(attr: &Attribute) -> Option<String> {
 }
 
 pub fn get_ty_path(ty: &Type) -> String {
-    match ty {
-        Type::Path(path) => {
-            let first = get_path_first(&path.path);
-            if first == "Option" {
-                let segments = &path.path.segments;
-                let segment = segments.first().unwrap();
-                if let PathSegment {
-                    ident,
-                    arguments:
-                        syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
-                            args,
-                            ..
-                        }),
-                } = segment
-                {
-                    if let syn::GenericArgument::Type(Type::Path(path)) = args.first().unwrap() {
-                        return get_path_first(&path.path);
-                    }
+    if let Type::Path(path) = ty {
+        let first = get_path_first(&path.path);
+        if first == "Option" {
+            let segments = &path.path.segments;
+            let segment = segments.first().unwrap();
+            if let PathSegment {
+                arguments:
+                    syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
+                        args, ..
+                    }),
+                ..
+            } = segment
+            {
+                if let syn::GenericArgument::Type(Type::Path(path)) = args.first().unwrap() {
+                    return get_path_first(&path.path);
                 }
-            } else {
-                return first.to_string();
             }
+        } else {
+            return first.to_string();
         }
-        _ => (),
     }
-    return "".to_string();
+    "".to_string()
 }
@@ -1,49 +1,218 @@
-use crate::utils::get_diag_type;
-use crate::utils::{replace_attr_name, replace_slug};
-use regex::Regex;
+use crate::error::{Diagnostic, Error};
+use crate::literal::quote;
+use crate::log::{self, Level};
+use crate::report::{AttrRecord, DiagRecord};
+use crate::rewrite::Rewriter;
+use crate::utils::{code_variables, fluent_variables, get_diag_type, line_col};
 use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::ops::Range;
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::visit::{self, Visit};
-use syn::{Attribute, ItemStruct};
+use syn::{Attribute, ItemStruct, Meta, Token};
+
+/// Attributes of `Diagnostic`, `Subdiagnostic` and `LintDiagnostic` derives
+/// which take a message, `suggestion_part` only carries code.
+pub(crate) const MESSAGE_ATTRS: [&str; 16] = [
+    "suggestion",
+    "suggestion_short",
+    "suggestion_hidden",
+    "suggestion_verbose",
+    "label",
+    "note",
+    "note_once",
+    "help",
+    "help_once",
+    "warning",
+    "multipart_suggestion",
+    "multipart_suggestion_short",
+    "multipart_suggestion_hidden",
+    "multipart_suggestion_verbose",
+    "diag",
+    "lint",
+];
+
+/// Attributes whose slug is the slug of the whole diagnostic when it comes
+/// first.
+pub(crate) const ITEM_SLUG_ATTRS: [&str; 2] = ["diag", "lint"];
+
+/// Arguments of a message attribute which are not a slug.
+const NON_SLUG_ARGS: [&str; 3] = ["style", "code", "applicability"];
+
+/// Where the message of a diagnostic attribute comes from.
+#[derive(Debug)]
+pub enum AttrSlug {
+    /// `#[label(slug)]` or `#[suggestion(slug, code = "..")]`, `sole` when the
+    /// slug is the only argument.
+    Slug {
+        name: String,
+        range: Range<usize>,
+        sole: bool,
+    },
+    /// `#[label]`, the message is the attribute of the same name, it is
+    /// inserted at `offset`, after the attribute name.
+    Bare { offset: usize },
+    /// `#[suggestion(code = "..")]`, the message is inserted at `offset`,
+    /// after the opening parenthesis.
+    Unnamed { offset: usize, empty: bool },
+}
+
+impl AttrSlug {
+    pub fn from_attr(attr: &Attribute) -> Option<Self> {
+        match &attr.meta {
+            Meta::Path(path) => Some(AttrSlug::Bare {
+                offset: path.span().byte_range().end,
+            }),
+            Meta::List(list) => {
+                let args = list
+                    .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                    .ok()?;
+                let offset = list.delimiter.span().open().byte_range().end;
+                for arg in args.iter() {
+                    let Meta::Path(path) = arg else {
+                        continue;
+                    };
+                    let Some(ident) = path.get_ident() else {
+                        continue;
+                    };
+                    let name = ident.to_string();
+                    if !NON_SLUG_ARGS.contains(&name.as_str()) {
+                        return Some(AttrSlug::Slug {
+                            name,
+                            range: ident.span().byte_range(),
+                            sole: args.len() == 1,
+                        });
+                    }
+                }
+                Some(AttrSlug::Unnamed {
+                    offset,
+                    empty: args.is_empty(),
+                })
+            }
+            Meta::NameValue(_) => None,
+        }
+    }
+
+    /// The slug to look up, the Fluent attribute of the derive for attributes
+    /// without a slug, such as `.suggestion` for `#[suggestion_short]`.
+    pub fn find_slug(&self, attr_name: &str) -> String {
+        match self {
+            AttrSlug::Slug { name, .. } => name.to_string(),
+            _ => format!(".{}", default_fluent_attr(attr_name)),
+        }
+    }
+}
+
+/// The Fluent attribute rustc falls back to for a message attribute without a
+/// slug, every kind of suggestion shares `.suggestion` and `#[warning]` uses
+/// `.warn`.
+pub(crate) fn default_fluent_attr(attr_name: &str) -> &str {
+    if is_suggestion(attr_name) {
+        "suggestion"
+    } else if attr_name == "warning" {
+        "warn"
+    } else {
+        attr_name.trim_end_matches("_once")
+    }
+}
+
+pub(crate) fn is_suggestion(attr_name: &str) -> bool {
+    attr_name.starts_with("suggestion") || attr_name.starts_with("multipart_suggestion")
+}
+/// A `#[suggestion_part(code = "..")]` field of a multipart suggestion.
+#[derive(Debug)]
+pub struct SuggestionPart {
+    pub field: String,
+    pub code: Option<String>,
+    /// Byte offset of the attribute.
+    pub offset: usize,
+}
+
+impl SuggestionPart {
+    fn from_field(field: &syn::Field) -> Option<Self> {
+        let attr = field
+            .attrs
+            .iter()
+            .find(|a| a.path().is_ident("suggestion_part"))?;
+        Some(SuggestionPart {
+            field: field.ident.as_ref()?.unraw().to_string(),
+            code: code_arg(attr),
+            offset: attr.span().byte_range().start,
+        })
+    }
+}
+
+/// The `code = ".."` argument of a suggestion attribute.
+fn code_arg(attr: &Attribute) -> Option<String> {
+    let args = attr
+        .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+        .ok()?;
+    args.iter().find_map(|arg| match arg {
+        Meta::NameValue(nv) if nv.path.is_ident("code") => match &nv.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(code),
+                ..
+            }) => Some(code.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
 #[derive(Debug)]
 pub struct ErrorStruct {
     pub slug: Option<String>,
-    pub attrs: HashMap<String, String>,
-    pub field_labels: Vec<(String, String)>,
     pub sub_diags: Vec<String>,
-    pub diag_type: String,
     pub diag_name: String,
-    pub parent_diag: Option<String>,
-    pub source: String,
-}
-
-impl ErrorStruct {
-    fn print(&self) {
-        eprintln!("--------------------------------");
-        eprintln!("slug: {:#?}", self.slug);
-        eprintln!("sub_diags: {:#?}", self.sub_diags);
-        eprintln!("diag_type: {:#?}", self.diag_type);
-        eprintln!("diag_name: {:#?}", self.diag_name);
-        eprintln!("parent_diag: {:#?}", self.parent_diag);
-        eprintln!("attrs: {:#?}", self.attrs);
-        eprintln!("field_labels: {:#?}", self.field_labels);
-        eprintln!("--------------------------------");
-    }
+    /// The enum of a variant, or the diagnostics which have this one as a
+    /// subdiagnostic, a shared subdiagnostic has several.
+    pub parent_diags: Vec<String>,
+    /// Byte range of the struct, enum or variant name.
+    pub span: Range<usize>,
+    /// The named fields without `#[skip_arg]`, which Fluent variables of the
+    /// messages refer to.
+    pub fields: Vec<String>,
+    pub parts: Vec<SuggestionPart>,
 }
 
 pub struct SynVisitor {
+    pub file_path: String,
     pub errors: Vec<ErrorStruct>,
     pub fluent_source: HashMap<String, crate::Entry>,
     pub file_source_code: String,
     pub cur_item_name: Vec<(String, String)>,
-    pub cur_source: Vec<String>,
+    pub cur_span: Vec<Range<usize>>,
     pub attrs: HashMap<String, Vec<Attribute>>,
-    pub path_replace: Vec<String>,
+    pub path_replace: Vec<(String, Range<usize>)>,
+    pub warnings: Vec<Diagnostic>,
+    pub records: Vec<DiagRecord>,
+    pub path_records: Vec<AttrRecord>,
+    /// The messages which the slugs resolved to, by entry slug and key.
+    pub used: BTreeSet<(String, String)>,
 }
 
 impl SynVisitor {
+    pub fn new(file_path: &str, code: &str) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            errors: vec![],
+            fluent_source: HashMap::new(),
+            file_source_code: code.to_string(),
+            cur_item_name: vec![],
+            cur_span: vec![],
+            attrs: HashMap::new(),
+            path_replace: vec![],
+            warnings: vec![],
+            records: vec![],
+            path_records: vec![],
+            used: BTreeSet::new(),
+        }
+    }
+
     pub fn init_with_syntax(&mut self, syntax: &syn::File) {
-        self.visit_file(&syntax);
+        self.visit_file(syntax);
         self.set_parent_diag();
     }
 
@@ -57,65 +226,48 @@ impl SynVisitor {
     }
 
     pub fn set_parent_diag(&mut self) {
-        let mut map = HashMap::new();
+        let mut parents = vec![];
         for error in self.errors.iter() {
             for sub_diag in error.sub_diags.iter() {
                 if let Some(index) = self.find_error_by_diag_name(sub_diag) {
-                    map.insert(index, error.diag_name.to_string());
-                } else {
-                    //unreachable!("not found sub_diag: {}", sub_diag);
+                    parents.push((index, error.diag_name.to_string()));
                 }
             }
         }
-        for (index, parent) in map.iter() {
-            self.errors[*index].parent_diag = Some(parent.to_string());
+        for (index, parent) in parents {
+            let parent_diags = &mut self.errors[index].parent_diags;
+            if !parent_diags.contains(&parent) {
+                parent_diags.push(parent);
+            }
         }
     }
 
-    pub fn set_fluent_source(&mut self, entries: &Vec<crate::Entry>) {
-        let mut entries = entries.clone();
-        let kv: HashMap<String, String> = entries
-            .iter()
-            .map(|e| (e.slug.to_string(), e.value.to_string()))
-            .collect();
-
+    pub fn set_fluent_source(&mut self, entries: &[crate::Entry]) {
+        // references between messages are already expanded by the parser
+        let mut entries = entries.to_vec();
         let childs = entries
             .iter()
             .map(|e| (e.slug.to_string(), e.value.to_string()))
             .collect::<Vec<_>>();
+        let manual = entries
+            .iter()
+            .flat_map(|e| e.manual.iter().filter(|(key, _)| key == &e.slug))
+            .cloned()
+            .collect::<Vec<_>>();
+        let locations = entries
+            .iter()
+            .flat_map(|e| e.locations.iter().filter(|(key, _)| key == &e.slug))
+            .cloned()
+            .collect::<Vec<_>>();
         let root_entry = crate::Entry {
             slug: "*root*".to_string(),
             value: "".to_string(),
-            childs: childs.clone(),
+            childs,
+            manual,
+            location: None,
+            locations,
         };
-        entries.push(root_entry.clone());
-
-        let fix_vars = |value: &str| {
-            let re = Regex::new(r"\{(\w+)\}").unwrap();
-            let mut change = vec![];
-            for mat in re.captures_iter(value) {
-                if let Some(v) = kv.get(&mat[1]) {
-                    eprintln!("now change {:#?} => {:#?}", &mat[1], v);
-                    change.push((mat[1].to_string(), v.to_string()));
-                }
-            }
-            let mut fixed = value.to_string();
-            for (mat, value) in change.iter() {
-                let from = format!("{{{}}}", mat);
-                fixed = fixed.replace(&from, value);
-            }
-            fixed
-        };
-        for i in 0..entries.len() {
-            let entry = entries.get_mut(i).unwrap();
-            let mut new_childs = vec![];
-            for (slug, value) in entry.childs.iter() {
-                let fixed = fix_vars(value);
-                new_childs.push((slug.to_string(), fixed));
-            }
-            entry.childs = new_childs;
-            entry.value = fix_vars(&entry.value);
-        }
+        entries.push(root_entry);
 
         for entry in entries.iter() {
             //eprintln!("insert entry slug {:#?} =>  {:#?}", entry.slug, entry);
@@ -127,124 +279,347 @@ impl SynVisitor {
     }
 
     fn get_entry_from_struct(&self, error_struct: &ErrorStruct) -> Option<&crate::Entry> {
-        let slug = error_struct.slug.clone();
-        if let Some(slug) = slug {
-            if let Some(entry) = self.fluent_source.get(&slug) {
-                return Some(entry);
+        self.entries(error_struct).into_iter().next()
+    }
+
+    /// The entries the messages of `error` come from: the entry of its slug,
+    /// or the entries of its parents, the root entry when it has neither.
+    fn entries(&self, error: &ErrorStruct) -> Vec<&crate::Entry> {
+        if let Some(entry) = error.slug.as_ref().and_then(|s| self.fluent_source.get(s)) {
+            return vec![entry];
+        }
+        let parents = self.parents(error);
+        if parents.is_empty() {
+            return self.fluent_source.get("*root*").into_iter().collect();
+        }
+        let mut entries: Vec<&crate::Entry> = vec![];
+        for entry in parents.into_iter().flat_map(|p| self.entries(p)) {
+            if !entries.iter().any(|e| e.slug == entry.slug) {
+                entries.push(entry);
             }
         }
+        entries
+    }
 
-        if let Some(parent_name) = &error_struct.parent_diag {
-            let parent_index = self.find_error_by_diag_name(parent_name).unwrap();
-            self.get_entry_from_struct(self.errors.get(parent_index).unwrap())
-        } else {
-            return self.fluent_source.get("*root*");
+    /// The diagnostics which `error` is a subdiagnostic of, those which are
+    /// not diagnostics of this file are left out.
+    fn parents(&self, error: &ErrorStruct) -> Vec<&ErrorStruct> {
+        error
+            .parent_diags
+            .iter()
+            .filter_map(|name| self.find_error_by_diag_name(name))
+            .map(|index| &self.errors[index])
+            .collect()
+    }
+
+    /// The entries which have the message for `slug`, one for each parent of
+    /// a shared subdiagnostic.
+    fn lookup(&self, error: &ErrorStruct, slug: &str) -> Vec<&crate::Entry> {
+        let entries = self
+            .entries(error)
+            .into_iter()
+            .filter(|e| e.resolve(slug).is_some())
+            .collect::<Vec<_>>();
+        if !entries.is_empty() {
+            return entries;
         }
+        let root = self.fluent_source.get("*root*").unwrap();
+        root.resolve(slug).map(|_| root).into_iter().collect()
     }
 
-    fn get_value(&self, error: &ErrorStruct, slug: &str) -> Option<String> {
-        if let Some(entry) = self.get_entry_from_struct(error) {
-            if let Some(v) = entry.get_value_from_slug(slug) {
-                return Some(v);
-            }
+    /// The text of the message for `slug`, or why it has to be converted by
+    /// hand, such as the parents of a shared subdiagnostic which disagree.
+    fn resolve(&self, error: &ErrorStruct, slug: &str) -> Option<Result<String, String>> {
+        let entries = self.lookup(error, slug);
+        let text = entries.first()?.resolve(slug)?;
+        if let Some(other) = entries.iter().find(|e| e.resolve(slug) != Some(text)) {
+            return Some(Err(format!(
+                "`{}` differs between `{}` and `{}`, which share this subdiagnostic, and needs manual conversion",
+                slug, entries[0].slug, other.slug
+            )));
+        }
+        Some(text.map(str::to_string).map_err(str::to_string))
+    }
+
+    /// Record that the message for `slug` of `entry` is used, as the slug of
+    /// its entry and its key.
+    fn mark_used(used: &mut BTreeSet<(String, String)>, entry: &crate::Entry, slug: &str) {
+        let Some(key) = entry.key(slug) else {
+            return;
+        };
+        if entry.slug == "*root*" {
+            used.insert((key.to_string(), key.to_string()));
         } else {
-            if let Some(parent_name) = &error.parent_diag {
-                let parent_index = self.find_error_by_diag_name(parent_name).unwrap();
-                if let Some(parent) =
-                    self.get_entry_from_struct(self.errors.get(parent_index).unwrap())
-                {
-                    if let Some(v) = parent.get_value_from_slug(slug) {
-                        return Some(v);
-                    }
-                }
+            used.insert((entry.slug.to_string(), key.to_string()));
+        }
+    }
+
+    fn location(&self, offset: usize) -> (usize, usize) {
+        line_col(&self.file_source_code, offset)
+    }
+
+    /// Log `message` for the source at `offset`, within the diagnostic `item`.
+    fn log(&self, level: Level, offset: usize, item: Option<&str>, message: fmt::Arguments) {
+        if !log::enabled(level) {
+            return;
+        }
+        let (line, column) = self.location(offset);
+        let context = match item {
+            Some(item) => format!("{}:{}:{}: {}", self.file_path, line, column, item),
+            None => format!("{}:{}:{}", self.file_path, line, column),
+        };
+        log::event(level, &context, message);
+    }
+
+    fn warn(&self, warnings: &mut Vec<Diagnostic>, offset: usize, warning: Diagnostic) {
+        self.log(
+            Level::Warning,
+            offset,
+            Some(&warning.item),
+            format_args!("{}", warning.message),
+        );
+        warnings.push(warning);
+    }
+
+    /// Whether `name` is a field of `error`, or of the diagnostics it is a
+    /// subdiagnostic of, which pass their fields on to it.
+    fn has_field(&self, error: &ErrorStruct, name: &str) -> bool {
+        if error.fields.iter().any(|f| f == name) {
+            return true;
+        }
+        self.parents(error)
+            .into_iter()
+            .any(|parent| self.has_field(parent, name))
+    }
+
+    /// Report every variable of the inlined `message` of the attribute `key`
+    /// which is neither a field of `error` nor of one of its parents.
+    fn check_variables(
+        &self,
+        error: &ErrorStruct,
+        offset: usize,
+        key: &str,
+        message: &str,
+        warnings: &mut Vec<Diagnostic>,
+    ) {
+        for var in fluent_variables(message) {
+            if !self.has_field(error, &var) {
+                let warning = Diagnostic::new(
+                    &error.diag_name,
+                    format!(
+                        "`{{${}}}` in the message of `#[{}]` is not a field",
+                        var, key
+                    ),
+                );
+                self.warn(warnings, offset, warning);
             }
         }
-        let root = self.fluent_source.get("*root*").unwrap();
-        root.get_value_from_slug(slug)
     }
 
-    pub fn gen_source_code(&self) -> String {
-        //let mut output = "".to_string();
-        let mut error_struct_outputs = vec![];
-        for error in self.errors.iter() {
-            //error.print();
-            let Some(entry) = self.get_entry_from_struct(error) else {
-                eprintln!(
-                    "no entry for error: {:#?} slug: {:?}",
-                    error.diag_name, error.slug
+    /// Check a multipart suggestion at `offset` against its
+    /// `#[suggestion_part]` fields, the code of every part may only
+    /// interpolate fields of the struct.
+    fn check_multipart(&self, error: &ErrorStruct, offset: usize, warnings: &mut Vec<Diagnostic>) {
+        let name = &error.diag_name;
+        if error.parts.is_empty() {
+            let warning = Diagnostic::new(
+                name,
+                "multipart suggestion without `#[suggestion_part]` fields".to_string(),
+            );
+            self.warn(warnings, offset, warning);
+        }
+        for part in error.parts.iter() {
+            let Some(code) = &part.code else {
+                let warning = Diagnostic::new(
+                    name,
+                    format!("`#[suggestion_part]` of `{}` has no code", part.field),
                 );
+                self.warn(warnings, part.offset, warning);
                 continue;
             };
-            let mut result = error.source.clone();
-            let slug = error.slug.clone();
-
-            let mut add_labels: Vec<(String, String)> = error
-                .attrs
-                .iter()
-                .map(|(k, v)| (k.to_string(), v.to_string()))
-                .collect();
-            add_labels.extend(error.field_labels.clone());
-
-            if let Some(slug) = slug {
-                let value = self.get_value(&error, &slug);
-                if let Some(slug_value) = value {
-                    add_labels.push((slug, slug_value));
+            for var in code_variables(code) {
+                if !self.has_field(error, &var) {
+                    let warning = Diagnostic::new(
+                        name,
+                        format!(
+                            "`{{{}}}` in the code of `{}` is not a field",
+                            var, part.field
+                        ),
+                    );
+                    self.warn(warnings, part.offset, warning);
                 }
             }
+        }
+    }
 
-            add_labels.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
-
-            for (name, value) in add_labels.iter() {
-                let find_slug = if value == "_" {
-                    format!(".{}", name)
+    pub fn gen_source_code(&mut self) -> Result<String, Error> {
+        let mut rewriter = Rewriter::new();
+        let mut warnings = vec![];
+        let mut records = vec![];
+        let mut used = BTreeSet::new();
+        for error in self.errors.iter() {
+            let (line, column) = self.location(error.span.start);
+            let mut record = DiagRecord {
+                name: error.diag_name.clone(),
+                slug: error.slug.clone(),
+                line,
+                column,
+                attrs: vec![],
+            };
+            let no_entry = self.get_entry_from_struct(error).is_none();
+            if no_entry {
+                let warning = Diagnostic::new(
+                    &error.diag_name,
+                    format!("no entry for slug {:?}", error.slug),
+                );
+                self.warn(&mut warnings, error.span.start, warning);
+            }
+            let attrs = self.attrs.get(&error.diag_name).map_or(&[][..], |a| a);
+            for attr in attrs.iter() {
+                let Some(key) = MESSAGE_ATTRS.iter().find(|key| attr.path().is_ident(key)) else {
+                    continue;
+                };
+                let Some(attr_slug) = AttrSlug::from_attr(attr) else {
+                    continue;
+                };
+                let find_slug = attr_slug.find_slug(key);
+                let offset = attr.span().byte_range().start;
+                let (line, column) = self.location(offset);
+                let entries = self.lookup(error, &find_slug);
+                for entry in entries.iter() {
+                    Self::mark_used(&mut used, entry, &find_slug);
+                }
+                let definition = entries.first().and_then(|e| e.locate(&find_slug)).cloned();
+                let text = if no_entry {
+                    Err(format!("no entry for slug {:?}", error.slug))
                 } else {
-                    value.to_string()
+                    self.resolve(error, &find_slug)
+                        .unwrap_or_else(|| Err(format!("no message found for `{}`", find_slug)))
                 };
-                let slug_value = self.get_value(&error, &find_slug);
-                eprintln!(
-                    "all_labels find_slug: {:#?} => slug_value: {:#?}",
-                    find_slug, slug_value
-                );
-                if let Some(slug_value) = slug_value {
-                    if value == "_" {
-                        result = replace_attr_name(&result, name, slug_value.as_str());
-                    } else {
-                        result = replace_slug(&result, &find_slug, slug_value.as_str());
+                match &text {
+                    Ok(message) => {
+                        self.log(
+                            Level::Info,
+                            offset,
+                            Some(&error.diag_name),
+                            format_args!("`{}` resolved by `{}`", key, find_slug),
+                        );
+                        self.check_variables(error, offset, key, message, &mut warnings);
+                        if key.starts_with("multipart_suggestion") {
+                            self.check_multipart(error, offset, &mut warnings);
+                        }
+                    }
+                    Err(reason) if !no_entry => {
+                        // a message which needs a manual conversion is located
+                        // by its reason, a missing one by the entry it should be in
+                        let entry = self.get_entry_from_struct(error);
+                        let reason = match entry.and_then(|e| e.location.as_ref()) {
+                            Some(location) if definition.is_none() => {
+                                format!("{}, see {}", reason, location)
+                            }
+                            _ => reason.clone(),
+                        };
+                        let warning = Diagnostic::new(&error.diag_name, reason);
+                        self.warn(&mut warnings, offset, warning);
+                    }
+                    Err(_) => {}
+                }
+                let value = text.as_deref().ok().map(quote);
+                record.attrs.push(AttrRecord {
+                    attr: key.to_string(),
+                    slug: find_slug,
+                    message: text,
+                    line,
+                    column,
+                    definition,
+                });
+                let Some(value) = value else {
+                    continue;
+                };
+                match attr_slug {
+                    AttrSlug::Slug { range, sole, .. } => {
+                        let text = if sole {
+                            value
+                        } else {
+                            format!("label = {}", value)
+                        };
+                        rewriter.replace(range, text);
+                    }
+                    AttrSlug::Bare { offset } => rewriter.insert(offset, format!("({})", value)),
+                    AttrSlug::Unnamed { offset, empty } => {
+                        let text = if empty {
+                            format!("label = {}", value)
+                        } else {
+                            format!("label = {}, ", value)
+                        };
+                        rewriter.insert(offset, text);
                     }
-                } else {
-                    eprintln!("not found slug_value: {}", find_slug);
-                    eprintln!(
-                        "not found slug_value find_slug: {:#?} => slug_value: {:#?}",
-                        find_slug, slug_value
-                    );
-                    //panic!("none error now");
                 }
             }
-            error_struct_outputs.push((error.source.to_string(), result));
-        }
-        // write the result to file
-        let mut output = self.file_source_code.to_string();
-        for (from, to) in error_struct_outputs.iter() {
-            output = output.replace(from, to);
+            records.push(record);
         }
 
         let root = self.fluent_source.get("*root*").unwrap();
         let mut cur_entry = root.clone();
-        for path in self.path_replace.iter() {
-            let elems = path.split("::").collect::<Vec<_>>();
+        for (path, range) in self.path_replace.iter() {
+            let elems = path.split("::").map(str::trim).collect::<Vec<_>>();
+            // the generated module is used by its path too, it is only
+            // counted as a use of the message
+            if let [.., "fluent_generated", slug] = elems[..] {
+                Self::mark_used(&mut used, root, slug);
+            }
             if elems.len() == 2 && elems[0] == "fluent" {
-                eprintln!("path: {:#?}", elems);
                 let slug = elems[1];
                 if let Some(entry) = self.fluent_source.get(slug) {
                     cur_entry = entry.clone();
                 }
-                let value = cur_entry.get_value_from_slug(slug).unwrap();
-                //let value = root.get_value_from_slug(slug).unwrap();
-                let replace = format!("DiagnosticMessage::Str(Cow::from({}))", &value);
-                output = output.replace(path, &replace);
+                // a slug without a message is reported, the path is left alone
+                let text = match cur_entry.resolve(slug) {
+                    Some(text) => {
+                        Self::mark_used(&mut used, &cur_entry, slug);
+                        text.map(str::to_string).map_err(str::to_string)
+                    }
+                    None => Err(format!("no message found for `{}`", slug)),
+                };
+                let (line, column) = self.location(range.start);
+                let definition = cur_entry.locate(slug);
+                self.path_records.push(AttrRecord {
+                    attr: "fluent".to_string(),
+                    slug: slug.to_string(),
+                    message: text.clone(),
+                    line,
+                    column,
+                    definition: definition.cloned(),
+                });
+                let text = match text {
+                    Ok(text) => text,
+                    Err(reason) => {
+                        let warning = Diagnostic::new(path, reason);
+                        self.warn(&mut warnings, range.start, warning);
+                        continue;
+                    }
+                };
+                self.log(
+                    Level::Info,
+                    range.start,
+                    None,
+                    format_args!("`{}` resolved", path),
+                );
+                let replace = format!("DiagnosticMessage::Str(Cow::from({}))", quote(&text));
+                rewriter.replace(range.clone(), replace);
             }
         }
-        return output;
+        self.warnings.extend(warnings);
+        self.records = records;
+        self.used = used;
+        rewriter.apply(&self.file_source_code).map_err(|offset| {
+            let (line, column) = line_col(&self.file_source_code, offset);
+            Error::OverlappingEdit {
+                path: self.file_path.clone(),
+                line,
+                column,
+            }
+        })
     }
 
     fn cur_diag_name(&self) -> Option<String> {
@@ -264,14 +639,11 @@ impl SynVisitor {
                 }
             }
         }
-        return None;
+        None
     }
 
-    fn process_attrs(&mut self, sub_diags: &Vec<String>) {
+    fn process_attrs(&mut self, sub_diags: &[String], fields: &syn::Fields) {
         let mut slug = None;
-        let diag_attrs = HashMap::new();
-        let mut field_labels = BTreeSet::new();
-        let mut diag_type = None;
 
         let Some(diag_name) = self.cur_diag_name() else {
             return;
@@ -280,51 +652,18 @@ impl SynVisitor {
         let Some(attrs) = self.attrs.get(&diag_name) else {
             return;
         };
-        if let Some(first_attr) = attrs.first() {
-            diag_type = get_diag_type(first_attr);
-        }
-        for attr in attrs.iter() {
-            if attr.path().is_ident("diag")
-                || attr.path().is_ident("multipart_suggestion")
-                || attr.path().is_ident("suggestion")
-            {
-                let _ = attr.parse_nested_meta(|meta| {
-                    let first_segment = meta.path.segments.first().unwrap();
-                    let _slug = first_segment.ident.to_string();
-                    if slug.is_none() {
-                        slug = Some(_slug);
-                    }
-                    Ok(())
-                });
-            }
+        // only items whose first attribute takes arguments, as a derive
+        // does, are diagnostics
+        if attrs.first().and_then(get_diag_type).is_none() {
+            return;
         }
         for attr in attrs.iter() {
-            let variants = vec![
-                "suggestion",
-                "label",
-                "note",
-                "help",
-                "multipart_suggestion",
-                "diag",
-            ];
-            for key in variants.iter() {
-                if attr.path().is_ident(key) {
-                    let mut added = false;
-                    let _ = attr.parse_nested_meta(|meta| {
-                        if let Some(slug_segment) = meta.path.segments.first() {
-                            let _slug = slug_segment.ident.to_string();
-                            if _slug != "style" && _slug != "code" && _slug != "applicability" {
-                                field_labels.insert((key.to_string(), _slug.to_string()));
-                                added = true;
-                            }
-                        } else {
-                            panic!("not found slug");
-                        }
-                        Ok(())
-                    });
-                    if !added {
-                        field_labels.insert((key.to_string(), "_".to_string()));
-                    }
+            let Some(key) = MESSAGE_ATTRS.iter().find(|key| attr.path().is_ident(key)) else {
+                continue;
+            };
+            if let Some(AttrSlug::Slug { name, .. }) = AttrSlug::from_attr(attr) {
+                if slug.is_none() && (ITEM_SLUG_ATTRS.contains(key) || is_suggestion(key)) {
+                    slug = Some(name);
                 }
             }
         }
@@ -335,103 +674,112 @@ impl SynVisitor {
         } else {
             None
         };
-        if let Some(diag_type) = diag_type {
-            let error_struct = ErrorStruct {
-                slug,
-                attrs: diag_attrs,
-                sub_diags: sub_diags.clone(),
-                field_labels: field_labels.into_iter().collect(),
-                diag_type,
-                diag_name,
-                parent_diag,
-                source: self.cur_source.last().unwrap().to_string(),
-            };
-            //eprintln!("error_struct: {:#?}", error_struct);
-            self.errors.push(error_struct);
-        }
+        let error_struct = ErrorStruct {
+            slug,
+            sub_diags: sub_diags.to_vec(),
+            diag_name,
+            parent_diags: parent_diag.into_iter().collect(),
+            span: self.cur_span.last().unwrap().clone(),
+            fields: fields
+                .iter()
+                .filter(|f| !f.attrs.iter().any(|a| a.path().is_ident("skip_arg")))
+                .filter_map(|f| f.ident.as_ref().map(|i| i.unraw().to_string()))
+                .collect(),
+            parts: fields
+                .iter()
+                .filter_map(SuggestionPart::from_field)
+                .collect(),
+        };
+        self.errors.push(error_struct);
     }
 }
 
 impl<'ast> Visit<'ast> for SynVisitor {
     fn visit_attribute(&mut self, i: &'ast Attribute) {
         if let Some(diag_name) = self.cur_diag_name() {
-            self.attrs
-                .entry(diag_name)
-                .or_insert_with(|| Vec::new())
-                .push(i.clone());
+            self.attrs.entry(diag_name).or_default().push(i.clone());
         }
         visit::visit_attribute(self, i);
     }
 
     fn visit_path(&mut self, i: &'ast syn::Path) {
-        eprintln!("visiting path: {:#?}", i);
         visit::visit_path(self, i);
         let source = i.span().source_text().unwrap().to_string();
-        eprintln!("path source: {}", source);
-        self.path_replace.push(source);
+        self.log(
+            Level::Trace,
+            i.span().byte_range().start,
+            self.cur_diag_name().as_deref(),
+            format_args!("path `{}`", source),
+        );
+        self.path_replace.push((source, i.span().byte_range()));
     }
 
     fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
-        eprintln!("Enum with name={:#?}", i.ident.to_string());
-        let span = i.span();
-        let source = i.span().source_text().unwrap().to_string();
+        self.log(
+            Level::Trace,
+            i.ident.span().byte_range().start,
+            Some(&i.ident.to_string()),
+            format_args!("visiting enum"),
+        );
         self.cur_item_name
             .push((i.ident.to_string(), "Enum".to_string()));
-        self.cur_source.push(source);
+        self.cur_span.push(i.ident.span().byte_range());
 
         self::visit::visit_item_enum(self, i);
-        self.process_attrs(&vec![]);
+        self.process_attrs(&[], &syn::Fields::Unit);
         self.cur_item_name.pop();
-        self.cur_source.pop();
+        self.cur_span.pop();
     }
 
     fn visit_variant(&mut self, i: &'ast syn::Variant) {
-        eprintln!("Variant with name={:#?}", i.ident.to_string());
-        let source = i.span().source_text().unwrap().to_string();
+        self.log(
+            Level::Trace,
+            i.ident.span().byte_range().start,
+            Some(&i.ident.to_string()),
+            format_args!("visiting variant"),
+        );
         self.cur_item_name
             .push((i.ident.to_string(), "Variant".to_string()));
-        self.cur_source.push(source);
+        self.cur_span.push(i.ident.span().byte_range());
 
         let mut sub_diags = vec![];
         for field in i.fields.iter() {
             for attr in field.attrs.iter() {
                 if attr.path().is_ident("subdiagnostic") {
-                    let field_name = field.ident.as_ref().unwrap().to_string();
-                    let field_ty = &field.ty;
-                    //eprintln!("subdiagnostic: {} {:#?}", field_name, field_ty);
-                    let subdiag_struct = crate::utils::get_ty_path(field_ty);
+                    let subdiag_struct = crate::utils::get_ty_path(&field.ty);
                     sub_diags.push(subdiag_struct);
                 }
             }
         }
         self::visit::visit_variant(self, i);
-        self.process_attrs(&sub_diags);
+        self.process_attrs(&sub_diags, &i.fields);
         self.cur_item_name.pop();
-        self.cur_source.pop();
+        self.cur_span.pop();
     }
 
     fn visit_item_struct(&mut self, i: &'ast ItemStruct) {
-        eprintln!("Struct with name={:#?}", i.ident.to_string());
+        self.log(
+            Level::Trace,
+            i.ident.span().byte_range().start,
+            Some(&i.ident.to_string()),
+            format_args!("visiting struct"),
+        );
         self.cur_item_name
             .push((i.ident.to_string(), "Struct".to_string()));
-        self.cur_source
-            .push(i.span().source_text().unwrap().to_string());
+        self.cur_span.push(i.ident.span().byte_range());
         let mut sub_diags = vec![];
         for field in i.fields.iter() {
             for attr in field.attrs.iter() {
                 if attr.path().is_ident("subdiagnostic") {
-                    let field_name = field.ident.as_ref().unwrap().to_string();
-                    let field_ty = &field.ty;
-                    //eprintln!("subdiagnostic: {} {:#?}", field_name, field_ty);
-                    let subdiag_struct = crate::utils::get_ty_path(field_ty);
+                    let subdiag_struct = crate::utils::get_ty_path(&field.ty);
                     sub_diags.push(subdiag_struct);
                 }
             }
         }
 
         self::visit::visit_item_struct(self, i);
-        self.process_attrs(&sub_diags);
+        self.process_attrs(&sub_diags, &i.fields);
         self.cur_item_name.pop();
-        self.cur_source.pop();
+        self.cur_span.pop();
     }
 }
@@ -22,7 +22,7 @@ pub(crate) struct AmbiguousPlus {
 }
 
 #[derive(Diagnostic)]
-#[diag(label = "expected a path on the left-hand side of `+`, not `{$ty}`" , code = "E0178")]
+#[diag(label = "expected a path on the left-hand side of `+`, not `{$ty}`", code = "E0178")]
 pub(crate) struct BadTypePlus {
     pub ty: String,
     #[primary_span]
@@ -34,7 +34,7 @@ pub(crate) struct BadTypePlus {
 #[derive(Subdiagnostic)]
 pub(crate) enum BadTypePlusSub {
     #[suggestion(
-        label = "try adding parentheses" ,
+        label = "try adding parentheses",
         code = "{sum_with_parens}",
         applicability = "machine-applicable"
     )]
@@ -65,7 +65,7 @@ pub(crate) struct BadQPathStage2 {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "types that don't start with an identifier need to be surrounded with angle brackets in qualified paths" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "types that don't start with an identifier need to be surrounded with angle brackets in qualified paths", applicability = "machine-applicable")]
 pub(crate) struct WrapType {
     #[suggestion_part(code = "<")]
     pub lo: Span,
@@ -89,7 +89,7 @@ pub(crate) struct IncorrectSemicolon<'a> {
 pub(crate) struct IncorrectUseOfAwait {
     #[primary_span]
     #[suggestion(
-        label = "`await` is not a method call, remove the parentheses" ,
+        label = "`await` is not a method call, remove the parentheses",
         code = "",
         applicability = "machine-applicable"
     )]
@@ -101,7 +101,7 @@ pub(crate) struct IncorrectUseOfAwait {
 pub(crate) struct IncorrectAwait {
     #[primary_span]
     pub span: Span,
-    #[suggestion(label = "`await` is a postfix operation" , code = "{expr}.await{question_mark}")]
+    #[suggestion(label = "`await` is a postfix operation", code = "{expr}.await{question_mark}")]
     pub sugg_span: (Span, Applicability),
     pub expr: String,
     pub question_mark: &'static str,
@@ -128,25 +128,25 @@ pub(crate) struct InvalidVariableDeclaration {
 #[derive(Subdiagnostic)]
 pub(crate) enum InvalidVariableDeclarationSub {
     #[suggestion(
-        label = "switch the order of `mut` and `let`" ,
+        label = "switch the order of `mut` and `let`",
         applicability = "maybe-incorrect",
         code = "let mut"
     )]
     SwitchMutLetOrder(#[primary_span] Span),
     #[suggestion(
-        label = "missing keyword" ,
+        label = "missing keyword",
         applicability = "machine-applicable",
         code = "let mut"
     )]
     MissingLet(#[primary_span] Span),
     #[suggestion(
-        label = "write `let` instead of `auto` to introduce a new variable" ,
+        label = "write `let` instead of `auto` to introduce a new variable",
         applicability = "machine-applicable",
         code = "let"
     )]
     UseLetNotAuto(#[primary_span] Span),
     #[suggestion(
-        label = "write `let` instead of `var` to introduce a new variable" ,
+        label = "write `let` instead of `var` to introduce a new variable",
         applicability = "machine-applicable",
         code = "let"
     )]
@@ -166,7 +166,7 @@ pub(crate) struct InvalidComparisonOperator {
 #[derive(Subdiagnostic)]
 pub(crate) enum InvalidComparisonOperatorSub {
     #[suggestion(
-        label = "`{$invalid}` is not a valid comparison operator, use `{$correct}`" ,
+        label = "`{$invalid}` is not a valid comparison operator, use `{$correct}`",
         style = "short",
         applicability = "machine-applicable",
         code = "{correct}"
@@ -195,14 +195,14 @@ pub(crate) struct InvalidLogicalOperator {
 #[derive(Subdiagnostic)]
 pub(crate) enum InvalidLogicalOperatorSub {
     #[suggestion(
-        label = "use `&&` to perform logical conjunction" ,
+        label = "use `&&` to perform logical conjunction",
         style = "short",
         applicability = "machine-applicable",
         code = "&&"
     )]
     Conjunction(#[primary_span] Span),
     #[suggestion(
-        label = "use `||` to perform logical disjunction" ,
+        label = "use `||` to perform logical disjunction",
         style = "short",
         applicability = "machine-applicable",
         code = "||"
@@ -231,7 +231,7 @@ pub(crate) struct NotAsNegationOperator {
 #[derive(Subdiagnostic)]
 pub enum NotAsNegationOperatorSub {
     #[suggestion(
-        label = "use `!` to perform logical negation or bitwise not" ,
+        label = "use `!` to perform logical negation or bitwise not",
         style = "short",
         applicability = "machine-applicable",
         code = "!"
@@ -239,7 +239,7 @@ pub enum NotAsNegationOperatorSub {
     SuggestNotDefault(#[primary_span] Span),
 
     #[suggestion(
-        label = "use `!` to perform bitwise not" ,
+        label = "use `!` to perform bitwise not",
         style = "short",
         applicability = "machine-applicable",
         code = "!"
@@ -247,7 +247,7 @@ pub enum NotAsNegationOperatorSub {
     SuggestNotBitwise(#[primary_span] Span),
 
     #[suggestion(
-        label = "use `!` to perform logical negation" ,
+        label = "use `!` to perform logical negation",
         style = "short",
         applicability = "machine-applicable",
         code = "!"
@@ -288,7 +288,7 @@ pub(crate) struct UnexpectedTokenAfterLabel {
     #[primary_span]
     #[label(r#"expected `while`, `for`, `loop` or `{"{"}` after a label"#)]
     pub span: Span,
-    #[suggestion(label = "consider removing the label" , style = "verbose", code = "")]
+    #[suggestion(label = "consider removing the label", style = "verbose", code = "")]
     pub remove_label: Option<Span>,
     #[subdiagnostic]
     pub enclose_in_block: Option<UnexpectedTokenAfterLabelSugg>,
@@ -296,7 +296,7 @@ pub(crate) struct UnexpectedTokenAfterLabel {
 
 #[derive(Subdiagnostic)]
 #[multipart_suggestion(
-    label = "consider enclosing expression in a block" ,
+    label = "consider enclosing expression in a block",
     applicability = "machine-applicable"
 )]
 pub(crate) struct UnexpectedTokenAfterLabelSugg {
@@ -366,7 +366,7 @@ pub(crate) struct InvalidBlockMacroSegment {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "wrap this in another block" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "wrap this in another block", applicability = "machine-applicable")]
 pub(crate) struct WrapInExplicitBlock {
     #[suggestion_part(code = "{{ ")]
     pub lo: Span,
@@ -403,7 +403,7 @@ pub struct TernaryOperator {
 
 #[derive(Subdiagnostic)]
 #[suggestion(
-    label = "remove the `if` if you meant to write a `let...else` statement" ,
+    label = "remove the `if` if you meant to write a `let...else` statement",
     applicability = "maybe-incorrect",
     code = ""
 )]
@@ -494,14 +494,14 @@ pub(crate) struct MissingInInForLoop {
 pub(crate) enum MissingInInForLoopSub {
     // Has been misleading, at least in the past (closed Issue #48492), thus maybe-incorrect
     #[suggestion(
-        label = "try using `in` here instead" ,
+        label = "try using `in` here instead",
         style = "short",
         applicability = "maybe-incorrect",
         code = "in"
     )]
     InNotOf(#[primary_span] Span),
     #[suggestion(
-        label = "try adding `in` here" ,
+        label = "try adding `in` here",
         style = "short",
         applicability = "maybe-incorrect",
         code = " in "
@@ -580,12 +580,12 @@ pub(crate) struct EqFieldInit {
 pub(crate) struct DotDotDot {
     #[primary_span]
     #[suggestion(
-        label = "use `..` for an exclusive range" ,
+        label = "use `..` for an exclusive range",
         applicability = "maybe-incorrect",
         code = ".."
     )]
     #[suggestion(
-        label = "or `..=` for an inclusive range" ,
+        label = "or `..=` for an inclusive range",
         applicability = "maybe-incorrect",
         code = "..="
     )]
@@ -617,10 +617,10 @@ pub(crate) struct UseEqInstead {
 }
 
 #[derive(Diagnostic)]
-#[diag(r#"expected { "`{}`" }, found `;`"#)]
+#[diag(r#"expected {"`{}`"}, found `;`"#)]
 pub(crate) struct UseEmptyBlockNotSemi {
     #[primary_span]
-    #[suggestion(label = r#"try using { "`{}`" } instead"#, style = "hidden", applicability = "machine-applicable", code = "{{}}")]
+    #[suggestion(label = r#"try using {"`{}`"} instead"#, style = "hidden", applicability = "machine-applicable", code = "{{}}")]
     pub span: Span,
 }
 
@@ -651,7 +651,7 @@ pub(crate) struct ShiftInterpretedAsGeneric {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "try shifting the cast value" , applicability = "machine-applicable")]
+#[multipart_suggestion(parse_suggestion, applicability = "machine-applicable")]
 pub(crate) struct ComparisonOrShiftInterpretedAsGenericSugg {
     #[suggestion_part(code = "(")]
     pub left: Span,
@@ -677,7 +677,7 @@ pub(crate) struct LeadingPlusNotSupported {
     #[label("unexpected `+`")]
     pub span: Span,
     #[suggestion(
-        label = "try removing the `+`" ,
+        label = "try removing the `+`",
         style = "verbose",
         code = "",
         applicability = "machine-applicable"
@@ -700,7 +700,7 @@ pub(crate) struct ParenthesesWithStructFields {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "if `{$type}` is a struct, use braces as delimiters" , applicability = "maybe-incorrect")]
+#[multipart_suggestion(label = "if `{$type}` is a struct, use braces as delimiters", applicability = "maybe-incorrect")]
 pub(crate) struct BracesForStructLiteral {
     #[suggestion_part(code = " {{ ")]
     pub first: Span,
@@ -709,7 +709,7 @@ pub(crate) struct BracesForStructLiteral {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "if `{$type}` is a function, use the arguments directly" , applicability = "maybe-incorrect")]
+#[multipart_suggestion(label = "if `{$type}` is a function, use the arguments directly", applicability = "maybe-incorrect")]
 pub(crate) struct NoFieldsForFnCall {
     #[suggestion_part(code = "")]
     pub fields: Vec<Span>,
@@ -726,7 +726,7 @@ pub(crate) struct LabeledLoopInBreak {
 
 #[derive(Subdiagnostic)]
 #[multipart_suggestion(
-    label = "wrap the expression in parentheses" ,
+    label = "wrap the expression in parentheses",
     applicability = "machine-applicable"
 )]
 pub(crate) struct WrapExpressionInParentheses {
@@ -746,7 +746,7 @@ pub(crate) struct ArrayBracketsInsteadOfSpaces {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "to make an array, use square brackets instead of curly braces" , applicability = "maybe-incorrect")]
+#[multipart_suggestion(label = "to make an array, use square brackets instead of curly braces", applicability = "maybe-incorrect")]
 pub(crate) struct ArrayBracketsInsteadOfSpacesSugg {
     #[suggestion_part(code = "[")]
     pub left: Span,
@@ -759,8 +759,8 @@ pub(crate) struct ArrayBracketsInsteadOfSpacesSugg {
 pub(crate) struct MatchArmBodyWithoutBraces {
     #[primary_span]
     #[label("{$num_statements ->
-[one] this statement is not surrounded by a body
-*[other] these statements are not surrounded by a body
+    [one] this statement is not surrounded by a body
+   *[other] these statements are not surrounded by a body
 }")]
     pub statements: Span,
     #[label("while parsing the `match` arm starting here")]
@@ -776,7 +776,7 @@ pub(crate) struct MatchArmBodyWithoutBraces {
 pub(crate) struct InclusiveRangeExtraEquals {
     #[primary_span]
     #[suggestion(
-        label = "use `..=` instead" ,
+        label = "use `..=` instead",
         style = "short",
         code = "..=",
         applicability = "maybe-incorrect"
@@ -796,12 +796,12 @@ pub(crate) struct InclusiveRangeMatchArrow {
 }
 
 #[derive(Diagnostic)]
-#[diag(label = "inclusive range with no end" , code = "E0586")]
+#[diag(label = "inclusive range with no end", code = "E0586")]
 #[note("inclusive ranges must be bounded at the end (`..=b` or `a..=b`)")]
 pub(crate) struct InclusiveRangeNoEnd {
     #[primary_span]
     #[suggestion(
-        label = "use `..` instead" ,
+        label = "use `..` instead",
         code = "..",
         applicability = "machine-applicable",
         style = "short"
@@ -812,9 +812,9 @@ pub(crate) struct InclusiveRangeNoEnd {
 #[derive(Subdiagnostic)]
 pub(crate) enum MatchArmBodyWithoutBracesSugg {
     #[multipart_suggestion(label = "surround the {$num_statements ->
-[one] statement
-*[other] statements
-} with a body" , applicability = "machine-applicable")]
+    [one] statement
+   *[other] statements
+} with a body", applicability = "machine-applicable")]
     AddBraces {
         #[suggestion_part(code = "{{ ")]
         left: Span,
@@ -822,7 +822,7 @@ pub(crate) enum MatchArmBodyWithoutBracesSugg {
         right: Span,
     },
     #[suggestion(
-        label = "replace `;` with `,` to end a `match` arm expression" ,
+        label = "replace `;` with `,` to end a `match` arm expression",
         code = ",",
         applicability = "machine-applicable"
     )]
@@ -842,7 +842,7 @@ pub(crate) struct StructLiteralNotAllowedHere {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "surround the struct literal with parentheses" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "surround the struct literal with parentheses", applicability = "machine-applicable")]
 pub(crate) struct StructLiteralNotAllowedHereSugg {
     #[suggestion_part(code = "(")]
     pub left: Span,
@@ -893,7 +893,7 @@ pub(crate) struct MismatchedClosingDelimiter {
 }
 
 #[derive(Diagnostic)]
-#[diag(label = "incorrect visibility restriction" , code = "E0704")]
+#[diag(label = "incorrect visibility restriction", code = "E0704")]
 #[help("some possible visibility restrictions are:
 `pub(crate)`: visible only on the current crate
 `pub(super)`: visible only in the current module's parent
@@ -920,7 +920,7 @@ pub(crate) struct ExpectedStatementAfterOuterAttr {
 }
 
 #[derive(Diagnostic)]
-#[diag(label = "found a documentation comment that doesn't document anything" , code = "E0585")]
+#[diag(label = "found a documentation comment that doesn't document anything", code = "E0585")]
 #[help("doc comments must come before what they document, if a comment was intended use `//`")]
 pub(crate) struct DocCommentDoesNotDocumentAnything {
     #[primary_span]
@@ -983,7 +983,7 @@ pub(crate) struct InvalidMetaItem {
 
 #[derive(Subdiagnostic)]
 #[suggestion(
-    label = "escape `{$ident_name}` to use it as an identifier" ,
+    label = "escape `{$ident_name}` to use it as an identifier",
     style = "verbose",
     applicability = "maybe-incorrect",
     code = "r#"
@@ -996,7 +996,7 @@ pub(crate) struct SuggEscapeIdentifier {
 
 #[derive(Subdiagnostic)]
 #[suggestion(
-    label = "remove this comma" ,
+    label = "remove this comma",
     applicability = "machine-applicable",
     code = ""
 )]
@@ -1007,7 +1007,7 @@ pub(crate) struct SuggRemoveComma {
 
 #[derive(Subdiagnostic)]
 #[suggestion(
-    label = "you might have meant to introduce a new binding" ,
+    label = "you might have meant to introduce a new binding",
     style = "verbose",
     applicability = "maybe-incorrect",
     code = "let "
@@ -1145,13 +1145,13 @@ impl<'a, G: EmissionGuarantee> IntoDiagnostic<'a, G> for ExpectedSemi {
 #[derive(Subdiagnostic)]
 pub(crate) enum ExpectedSemiSugg {
     #[suggestion(
-        label = "change this to `;`" ,
+        label = "change this to `;`",
         code = ";",
         applicability = "machine-applicable"
     )]
     ChangeToSemi(#[primary_span] Span),
     #[suggestion(
-        label = "add `;` here" ,
+        label = "add `;` here",
         style = "short",
         code = ";",
         applicability = "machine-applicable"
@@ -1169,7 +1169,7 @@ pub(crate) struct StructLiteralBodyWithoutPath {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "you might have forgotten to add the struct literal inside the block" , applicability = "has-placeholders")]
+#[multipart_suggestion(label = "you might have forgotten to add the struct literal inside the block", applicability = "has-placeholders")]
 pub(crate) struct StructLiteralBodyWithoutPathSugg {
     #[suggestion_part(code = "{{ SomeStruct ")]
     pub before: Span,
@@ -1187,7 +1187,7 @@ pub(crate) struct StructLiteralNeedingParens {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "you might need to surround the struct literal with parentheses" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "you might need to surround the struct literal with parentheses", applicability = "machine-applicable")]
 pub(crate) struct StructLiteralNeedingParensSugg {
     #[suggestion_part(code = "(")]
     pub before: Span,
@@ -1197,14 +1197,14 @@ pub(crate) struct StructLiteralNeedingParensSugg {
 
 #[derive(Diagnostic)]
 #[diag("{$num_extra_brackets ->
-[one] unmatched angle bracket
-*[other] unmatched angle brackets
+    [one] unmatched angle bracket
+   *[other] unmatched angle brackets
 }")]
 pub(crate) struct UnmatchedAngleBrackets {
     #[primary_span]
     #[suggestion(label = "{$num_extra_brackets ->
-[one] remove extra angle bracket
-*[other] remove extra angle brackets
+    [one] remove extra angle bracket
+   *[other] remove extra angle brackets
 }", code = "", applicability = "machine-applicable")]
     pub span: Span,
     pub num_extra_brackets: usize,
@@ -1220,7 +1220,7 @@ pub(crate) struct GenericParamsWithoutAngleBrackets {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "surround the type parameters with angle brackets" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "surround the type parameters with angle brackets", applicability = "machine-applicable")]
 pub(crate) struct GenericParamsWithoutAngleBracketsSugg {
     #[suggestion_part(code = "<")]
     pub left: Span,
@@ -1234,7 +1234,7 @@ pub(crate) struct ComparisonOperatorsCannotBeChained {
     #[primary_span]
     pub span: Vec<Span>,
     #[suggestion(
-        label = "use `::<...>` instead of `<...>` to specify lifetime, type, or const arguments" ,
+        label = "use `::<...>` instead of `<...>` to specify lifetime, type, or const arguments",
         style = "verbose",
         code = "::",
         applicability = "maybe-incorrect"
@@ -1250,7 +1250,7 @@ pub(crate) struct ComparisonOperatorsCannotBeChained {
 #[derive(Subdiagnostic)]
 pub(crate) enum ComparisonOperatorsCannotBeChainedSugg {
     #[suggestion(
-        label = "split the comparison into two" ,
+        label = "split the comparison into two",
         style = "verbose",
         code = " && {middle_term}",
         applicability = "maybe-incorrect"
@@ -1260,7 +1260,7 @@ pub(crate) enum ComparisonOperatorsCannotBeChainedSugg {
         span: Span,
         middle_term: String,
     },
-    #[multipart_suggestion(label = "parenthesize the comparison" , applicability = "maybe-incorrect")]
+    #[multipart_suggestion(label = "parenthesize the comparison", applicability = "maybe-incorrect")]
     Parenthesize {
         #[suggestion_part(code = "(")]
         left: Span,
@@ -1280,7 +1280,7 @@ pub(crate) struct QuestionMarkInType {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "if you meant to express that the type might not contain a value, use the `Option` wrapper type" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "if you meant to express that the type might not contain a value, use the `Option` wrapper type", applicability = "machine-applicable")]
 pub(crate) struct QuestionMarkInTypeSugg {
     #[suggestion_part(code = "Option<")]
     pub left: Span,
@@ -1298,7 +1298,7 @@ pub(crate) struct ParenthesesInForHead {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "remove parentheses in `for` loop" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "remove parentheses in `for` loop", applicability = "machine-applicable")]
 pub(crate) struct ParenthesesInForHeadSugg {
     #[suggestion_part(code = "{left_snippet}")]
     pub left: Span,
@@ -1325,7 +1325,7 @@ pub(crate) struct AttributeOnParamType {
 }
 
 #[derive(Diagnostic)]
-#[diag(label = "patterns aren't allowed in methods without bodies" , code = "E0642")]
+#[diag(label = "patterns aren't allowed in methods without bodies", code = "E0642")]
 pub(crate) struct PatternMethodParamWithoutBody {
     #[primary_span]
     #[suggestion(label = "give this argument a name or use an underscore to ignore it", code = "_", applicability = "machine-applicable")]
@@ -1350,7 +1350,7 @@ pub(crate) struct ConstGenericWithoutBraces {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "enclose the `const` expression in braces" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "enclose the `const` expression in braces", applicability = "machine-applicable")]
 pub(crate) struct ConstGenericWithoutBracesSugg {
     #[suggestion_part(code = "{{ ")]
     pub left: Span,
@@ -1370,7 +1370,7 @@ pub(crate) struct UnexpectedConstParamDeclaration {
 
 #[derive(Subdiagnostic)]
 pub(crate) enum UnexpectedConstParamDeclarationSugg {
-    #[multipart_suggestion(label = "`const` parameters must be declared for the `impl`" , applicability = "machine-applicable")]
+    #[multipart_suggestion(label = "`const` parameters must be declared for the `impl`", applicability = "machine-applicable")]
     AddParam {
         #[suggestion_part(code = "<{snippet}>")]
         impl_generics: Span,
@@ -1379,7 +1379,7 @@ pub(crate) enum UnexpectedConstParamDeclarationSugg {
         snippet: String,
         ident: String,
     },
-    #[multipart_suggestion(label = "`const` parameters must be declared for the `impl`" , applicability = "machine-applicable")]
+    #[multipart_suggestion(label = "`const` parameters must be declared for the `impl`", applicability = "machine-applicable")]
     AppendParam {
         #[suggestion_part(code = ", {snippet}")]
         impl_generics_end: Span,
@@ -1431,12 +1431,12 @@ pub(crate) struct FnPtrWithGenerics {
 
 #[derive(Subdiagnostic)]
 #[multipart_suggestion(label = "consider moving the lifetime {$arity ->
-[one] parameter
-*[other] parameters
+    [one] parameter
+    *[other] parameters
 } to {$for_param_list_exists ->
-[true] the
-*[false] a
-} `for` parameter list" , applicability = "maybe-incorrect")]
+    [true] the
+    *[false] a
+} `for` parameter list", applicability = "maybe-incorrect")]
 pub(crate) struct FnPtrWithGenericsSugg {
     #[suggestion_part(code = "{snippet}")]
     pub left: Span,
@@ -1539,7 +1539,7 @@ pub(crate) struct WhereClauseBeforeTupleStructBody {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "move the body before the where clause" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "move the body before the where clause", applicability = "machine-applicable")]
 pub(crate) struct WhereClauseBeforeTupleStructBodySugg {
     #[suggestion_part(code = "{snippet}")]
     pub left: Span,
@@ -1549,7 +1549,7 @@ pub(crate) struct WhereClauseBeforeTupleStructBodySugg {
 }
 
 #[derive(Diagnostic)]
-#[diag(label = "`async fn` is not permitted in Rust 2015" , code = "E0670")]
+#[diag(label = "`async fn` is not permitted in Rust 2015", code = "E0670")]
 pub(crate) struct AsyncFnIn2015 {
     #[primary_span]
     #[label("to use `async fn`, switch to Rust 2018 or later")]
@@ -1642,7 +1642,7 @@ pub(crate) enum MissingKeywordForItemDefinition {
 #[derive(Subdiagnostic)]
 pub(crate) enum AmbiguousMissingKwForItemSub {
     #[suggestion(
-        label = "if you meant to call a macro, try" ,
+        label = "if you meant to call a macro, try",
         applicability = "maybe-incorrect",
         code = "{snippet}!"
     )]
@@ -1668,13 +1668,13 @@ pub(crate) struct MissingFnParams {
 pub(crate) struct MissingTraitInTraitImpl {
     #[primary_span]
     #[suggestion(
-        label = "add a trait here" ,
+        label = "add a trait here",
         code = " Trait ",
         applicability = "has-placeholders"
     )]
     pub span: Span,
     #[suggestion(
-        label = "for an inherent impl, drop this `for`" ,
+        label = "for an inherent impl, drop this `for`",
         code = "",
         applicability = "maybe-incorrect"
     )]
@@ -1747,7 +1747,7 @@ pub(crate) struct ExternCrateNameWithDashes {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "if the original crate name uses dashes you need to use underscores in the code" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "if the original crate name uses dashes you need to use underscores in the code", applicability = "machine-applicable")]
 pub(crate) struct ExternCrateNameWithDashesSugg {
     #[suggestion_part(code = "_")]
     pub dashes: Vec<Span>,
@@ -1918,8 +1918,8 @@ pub struct CannotBeRawIdent {
 
 #[derive(Diagnostic)]
 #[diag("bare CR not allowed in {$block ->
-[true] block doc-comment
-*[false] doc-comment
+    [true] block doc-comment
+    *[false] doc-comment
 }")]
 pub struct CrDocComment {
     #[primary_span]
@@ -1928,7 +1928,7 @@ pub struct CrDocComment {
 }
 
 #[derive(Diagnostic)]
-#[diag(label = "no valid digits found for number" , code = "E0768")]
+#[diag(label = "no valid digits found for number", code = "E0768")]
 pub struct NoDigitsLiteral {
     #[primary_span]
     pub span: Span,
@@ -1959,7 +1959,7 @@ pub struct FloatLiteralUnsupportedBase {
 
 #[derive(Diagnostic)]
 #[diag("prefix `{$prefix}` is unknown")]
-#[note(" prefixed identifiers and literals are reserved since Rust 2021")]
+#[note("prefixed identifiers and literals are reserved since Rust 2021")]
 pub struct UnknownPrefix<'a> {
     #[primary_span]
     #[label("unknown prefix")]
@@ -1978,14 +1978,14 @@ pub struct MacroExpandsToAdtField<'a> {
 #[derive(Subdiagnostic)]
 pub enum UnknownPrefixSugg {
     #[suggestion(
-        label = "use `br` for a raw byte string" ,
+        label = "use `br` for a raw byte string",
         code = "br",
         applicability = "maybe-incorrect",
         style = "verbose"
     )]
     UseBr(#[primary_span] Span),
     #[suggestion(
-        label = "consider inserting whitespace here" ,
+        label = "consider inserting whitespace here",
         code = " ",
         applicability = "maybe-incorrect",
         style = "verbose"
@@ -2018,7 +2018,7 @@ pub struct UnknownTokenStart {
 #[derive(Subdiagnostic)]
 pub enum TokenSubstitution {
     #[suggestion(
-        label = "Unicode characters '“' (Left Double Quotation Mark) and '”' (Right Double Quotation Mark) look like '{$ascii_str}' ({$ascii_name}), but are not" ,
+        label = "Unicode characters '“' (Left Double Quotation Mark) and '”' (Right Double Quotation Mark) look like '{$ascii_str}' ({$ascii_name}), but are not",
         code = "{suggestion}",
         applicability = "maybe-incorrect"
     )]
@@ -2030,7 +2030,7 @@ pub enum TokenSubstitution {
         ascii_name: &'static str,
     },
     #[suggestion(
-        label = "Unicode character '{$ch}' ({$u_name}) looks like '{$ascii_str}' ({$ascii_name}), but it is not" ,
+        label = "Unicode character '{$ch}' ({$u_name}) looks like '{$ascii_str}' ({$ascii_name}), but it is not",
         code = "{suggestion}",
         applicability = "maybe-incorrect"
     )]
@@ -2047,8 +2047,8 @@ pub enum TokenSubstitution {
 
 #[derive(Subdiagnostic)]
 #[note("character appears {$repeats ->
-[one] once more
-*[other] {$repeats} more times
+    [one] once more
+    *[other] {$repeats} more times
 }")]
 pub struct UnknownTokenRepeat {
     pub repeats: usize,
@@ -2079,7 +2079,7 @@ pub enum UnescapeError {
         #[primary_span]
         span: Span,
         #[suggestion(
-            label = "escape the character" ,
+            label = "escape the character",
             applicability = "machine-applicable",
             code = "{escaped_sugg}"
         )]
@@ -2088,13 +2088,13 @@ pub enum UnescapeError {
         escaped_msg: String,
         byte: bool,
     },
-    #[diag("{$double_quotes ->
+    #[diag(r"{$double_quotes ->
 [true] bare CR not allowed in string, use `\r` instead
 *[false] character constant must be escaped: `\r`
 }")]
     BareCr {
         #[primary_span]
-        #[suggestion(label = "escape the character" , applicability = "machine-applicable", code = "\\r")]
+        #[suggestion(label = "escape the character", applicability = "machine-applicable", code = "\\r")]
         span: Span,
         double_quotes: bool,
     },
@@ -2119,7 +2119,7 @@ pub enum UnescapeError {
     #[diag("out of range hex escape")]
     OutOfRangeHexEscape(
         #[primary_span]
-        #[label("must be a character in the range [\x00-\x7f]")]
+        #[label(r"must be a character in the range [\x00-\x7f]")]
         Span,
     ),
     #[diag("invalid start of unicode escape: `_`")]
@@ -2141,7 +2141,7 @@ pub enum UnescapeError {
         #[label(r#"missing a closing `{"}"}`"#)]
         Span,
         #[suggestion(
-            label = "terminate the unicode escape" ,
+            label = "terminate the unicode escape",
             code = "}}",
             applicability = "maybe-incorrect",
             style = "verbose"
@@ -2210,7 +2210,7 @@ pub enum UnescapeError {
 #[derive(Subdiagnostic)]
 pub enum MoreThanOneCharSugg {
     #[suggestion(
-        label = "consider using the normalized form `{$ch}` of this character" ,
+        label = "consider using the normalized form `{$ch}` of this character",
         code = "{normalized}",
         applicability = "machine-applicable"
     )]
@@ -2220,7 +2220,7 @@ pub enum MoreThanOneCharSugg {
         ch: String,
         normalized: String,
     },
-    #[suggestion(label = "consider removing the non-printing characters" , code = "{ch}", applicability = "maybe-incorrect")]
+    #[suggestion(label = "consider removing the non-printing characters", code = "{ch}", applicability = "maybe-incorrect")]
     RemoveNonPrinting {
         #[primary_span]
         span: Span,
@@ -2230,7 +2230,7 @@ pub enum MoreThanOneCharSugg {
         label = "if you meant to write a {$is_byte ->
 [true] byte string
 *[false] `str`
-} literal, use double quotes" ,
+} literal, use double quotes",
         code = "{sugg}",
         applicability = "machine-applicable"
     )]
@@ -2266,7 +2266,7 @@ pub enum MoreThanOneCharNote {
 #[derive(Subdiagnostic)]
 pub enum NoBraceUnicodeSub {
     #[suggestion(
-        label = "format of unicode escape sequences uses braces" ,
+        label = "format of unicode escape sequences uses braces",
         code = "{suggestion}",
         applicability = "maybe-incorrect"
     )]
@@ -2282,7 +2282,7 @@ pub enum NoBraceUnicodeSub {
 #[derive(Subdiagnostic)]
 pub(crate) enum TopLevelOrPatternNotAllowedSugg {
     #[suggestion(
-        label = "remove the `|`" ,
+        label = "remove the `|`",
         code = "{pat}",
         applicability = "machine-applicable"
     )]
@@ -2292,7 +2292,7 @@ pub(crate) enum TopLevelOrPatternNotAllowedSugg {
         pat: String,
     },
     #[suggestion(
-        label = "wrap the pattern in parentheses" ,
+        label = "wrap the pattern in parentheses",
         code = "({pat})",
         applicability = "machine-applicable"
     )]
@@ -2517,7 +2517,7 @@ pub(crate) struct FnPointerCannotBeAsync {
 }
 
 #[derive(Diagnostic)]
-#[diag(label = "C-variadic type `...` may not be nested inside another type" , code = "E0743")]
+#[diag(label = "C-variadic type `...` may not be nested inside another type", code = "E0743")]
 pub(crate) struct NestedCVariadicType {
     #[primary_span]
     pub span: Span,
@@ -2798,7 +2798,7 @@ pub(crate) struct FunctionBodyEqualsExpr {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = r#"surround the expression with `{"{"}` and `{"}"}` instead of `=` and `;`"# , applicability = "machine-applicable")]
+#[multipart_suggestion(label = r#"surround the expression with `{"{"}` and `{"}"}` instead of `=` and `;`"#, applicability = "machine-applicable")]
 pub(crate) struct FunctionBodyEqualsExprSugg {
     #[suggestion_part(code = "{{")]
     pub eq: Span,
@@ -2854,7 +2854,7 @@ pub(crate) struct IncorrectParensTraitBounds {
 
 #[derive(Subdiagnostic)]
 #[multipart_suggestion(
-    label = "fix the parentheses" ,
+    label = "fix the parentheses",
     applicability = "machine-applicable"
 )]
 pub(crate) struct IncorrectParensTraitBoundsSugg {
@@ -2892,7 +2892,7 @@ pub(crate) struct CfgAttrBadDelim {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "the delimiters should be `(` and `)`" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "the delimiters should be `(` and `)`", applicability = "machine-applicable")]
 pub(crate) struct MetaBadDelimSugg {
     #[suggestion_part(code = "(")]
     pub open: Span,
@@ -2947,7 +2947,7 @@ pub(crate) struct WhereClauseBeforeConstBody {
 }
 
 #[derive(Subdiagnostic)]
-#[multipart_suggestion(label = "move the body before the where clause" , applicability = "machine-applicable")]
+#[multipart_suggestion(label = "move the body before the where clause", applicability = "machine-applicable")]
 pub(crate) struct WhereClauseBeforeConstBodySugg {
     #[suggestion_part(code = "= {snippet} ")]
     pub left: Span,
@@ -2962,7 +2962,7 @@ pub(crate) struct GenericArgsInPatRequireTurbofishSyntax {
     #[primary_span]
     pub span: Span,
     #[suggestion(
-        label = "use `::<...>` instead of `<...>` to specify lifetime, type, or const arguments" ,
+        label = "use `::<...>` instead of `<...>` to specify lifetime, type, or const arguments",
         style = "verbose",
         code = "::",
         applicability = "maybe-incorrect"
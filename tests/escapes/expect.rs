@@ -0,0 +1,67 @@
+#[derive(Diagnostic)]
+#[diag(r#"<assignment> ... else {"{"} ... {"}"} is not allowed"#)]
+pub(crate) struct AssignmentElseNotAllowed {
+    #[primary_span]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(r#"expected `{"{"}`, found {$first_tok}"#)]
+pub(crate) struct ExpectedElseBlock {
+    #[primary_span]
+    pub first_tok_span: Span,
+    pub first_tok: String,
+    #[label("expected an `if` or a block after this `else`")]
+    pub else_span: Span,
+    #[suggestion(label = "add an `if` if this is the condition of a chained `else if` statement", applicability = "maybe-incorrect", code = "if ")]
+    pub condition_start: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag("`gen` blocks are not yet implemented")]
+#[help(r#"for now you can use `gen {"{}"}` blocks and return `impl Iterator` instead"#)]
+pub(crate) struct GenBlock {
+    #[primary_span]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(r#"expected {"`{}`"}, found `;`"#)]
+pub(crate) struct ExpectedSemiFoundStr {
+    #[primary_span]
+    #[suggestion(label = r#"try using {"`{}`"} instead"#, style = "hidden", applicability = "machine-applicable", code = "{{}}")]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag("invalid unicode character escape")]
+pub(crate) struct UnicodeEscape {
+    #[primary_span]
+    pub span: Span,
+    #[help(r#"format of unicode escape sequences is `\u{"{...}"}`"#)]
+    pub help: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(r#"found " where a \ was expected"#)]
+pub(crate) struct Quoted {
+    #[primary_span]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag("expected `else`, found `{$token}`")]
+pub(crate) struct LiteralText {
+    #[primary_span]
+    pub span: Span,
+    pub token: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(r#"{" "}leading and trailing{" "}"#)]
+#[note(r#"this attribute continues
+{"["}on the next line, [inline is plain"#)]
+pub(crate) struct Edges {
+    #[primary_span]
+    pub span: Span,
+}
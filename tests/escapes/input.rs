@@ -0,0 +1,66 @@
+#[derive(Diagnostic)]
+#[diag(parse_assignment_else_not_allowed)]
+pub(crate) struct AssignmentElseNotAllowed {
+    #[primary_span]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(parse_expected_else_block)]
+pub(crate) struct ExpectedElseBlock {
+    #[primary_span]
+    pub first_tok_span: Span,
+    pub first_tok: String,
+    #[label]
+    pub else_span: Span,
+    #[suggestion(applicability = "maybe-incorrect", code = "if ")]
+    pub condition_start: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(parse_gen_block)]
+#[help]
+pub(crate) struct GenBlock {
+    #[primary_span]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(parse_expected_semi_found_str)]
+pub(crate) struct ExpectedSemiFoundStr {
+    #[primary_span]
+    #[suggestion(style = "hidden", applicability = "machine-applicable", code = "{{}}")]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(parse_unicode_escape)]
+pub(crate) struct UnicodeEscape {
+    #[primary_span]
+    pub span: Span,
+    #[help(parse_format_of_unicode)]
+    pub help: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(parse_quoted)]
+pub(crate) struct Quoted {
+    #[primary_span]
+    pub span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(parse_literal_text)]
+pub(crate) struct LiteralText {
+    #[primary_span]
+    pub span: Span,
+    pub token: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(parse_edges)]
+#[note]
+pub(crate) struct Edges {
+    #[primary_span]
+    pub span: Span,
+}
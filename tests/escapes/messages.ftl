@@ -0,0 +1,23 @@
+parse_assignment_else_not_allowed = <assignment> ... else {"{"} ... {"}"} is not allowed
+
+parse_expected_else_block = expected `{"{"}`, found {$first_tok}
+    .label = expected an `if` or a block after this `else`
+    .suggestion = add an `if` if this is the condition of a chained `else if` statement
+
+parse_gen_block = `gen` blocks are not yet implemented
+    .help = for now you can use `gen {"{}"}` blocks and return `impl Iterator` instead
+
+parse_expected_semi_found_str = expected {"`{}`"}, found `;`
+    .suggestion = try using { "`{}`" } instead
+
+parse_unicode_escape = invalid unicode character escape
+    .format_of_unicode = format of unicode escape sequences is `\u{"{...}"}`
+
+parse_quoted = found {"\""} where a {"\\"} was expected
+
+parse_literal_text = expected {"`"}else{"`"}, found {"`"}{$token}{"\U000060"}
+
+parse_edges = {" "}leading and trailing{" "}
+    .note =
+        this attribute continues
+        {"["}on the next line, {"["}inline is plain